@@ -5,19 +5,19 @@
 //! complex types (tuples) used in events. This module fills that gap by:
 //!
 //! 1. Extracting and generating Solidity struct definitions from event inputs
-//! 2. Handling nested struct definitions
+//! 2. Handling nested struct definitions and arrays of structs, ordered so every struct is declared
+//!    after the structs it uses
 //! 3. Producing valid Solidity event definitions that reference these structs
-use alloy_json_abi::{Event, EventParam, InternalType};
-use std::collections::HashMap;
+use alloy_json_abi::{Event, InternalType, Param};
 use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SolStruct {
     pub name: String,
     pub fields: Vec<SolField>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SolField {
     pub name: String,
     pub ty: String,
@@ -27,6 +27,7 @@ pub struct SolField {
 pub struct SolEvent {
     pub name: String,
     pub params: Vec<SolEventParam>,
+    pub anonymous: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -60,76 +61,364 @@ impl fmt::Display for SolEvent {
                 )
             })
             .collect();
-        write!(f, "event {}({});", self.name, params.join(", "))
+        let anonymous = if self.anonymous { " anonymous" } else { "" };
+        write!(f, "event {}({}){};", self.name, params.join(", "), anonymous)
+    }
+}
+
+/// Solidity keywords (including reserved ones) that ABIs may still use as parameter names.
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "abstract", "address", "after", "alias", "anonymous", "apply", "as", "assembly", "auto", "bool",
+    "break", "byte", "bytes", "calldata", "case", "catch", "constant", "constructor", "continue",
+    "contract", "copyof", "days", "default", "define", "delete", "do", "else", "emit", "enum",
+    "ether", "event", "external", "fallback", "false", "final", "for", "function", "gwei", "hex",
+    "hours", "if", "immutable", "implements", "import", "in", "indexed", "inline", "int",
+    "interface", "internal", "is", "let", "library", "macro", "mapping", "match", "memory",
+    "minutes", "modifier", "mutable", "new", "null", "of", "override", "partial", "payable",
+    "pragma", "private", "promise", "public", "pure", "receive", "reference", "relocatable",
+    "return", "returns", "sealed", "seconds", "sizeof", "static", "storage", "string", "struct",
+    "super", "supports", "switch", "this", "true", "try", "type", "typedef", "typeof", "uint",
+    "unchecked", "unicode", "using", "var", "view", "virtual", "weeks", "wei", "while", "years",
+];
+
+/// A valid identifier for the `index`th parameter: `arg{index}` if it's unnamed, and suffixed with
+/// `_` if it's a keyword.
+fn param_name(name: &str, index: usize) -> String {
+    let is_sized_type = ["int", "uint", "bytes", "fixed", "ufixed"].iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit() || c == 'x'))
+    });
+    if name.is_empty() {
+        format!("arg{}", index)
+    } else if KEYWORDS.contains(&name) || is_sized_type {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Keeps the first event of every overloaded name, since `events.sol` can only declare one event
+/// per name and renaming one would change its topic. Returns the kept and the dropped events.
+pub fn pick_overloads(events: Vec<Event>) -> (Vec<Event>, Vec<Event>) {
+    let mut kept: Vec<Event> = Vec::new();
+    let mut dropped = Vec::new();
+    for event in events {
+        if kept.iter().any(|e| e.name == event.name) {
+            dropped.push(event);
+        } else {
+            kept.push(event);
+        }
     }
+    (kept, dropped)
 }
 
+/// Splits an array type like `Pool[][3]` into its base type and array suffix (`"Pool"`, `"[][3]"`).
+fn split_array(ty: &str) -> (&str, &str) {
+    ty.split_at(ty.find('[').unwrap_or(ty.len()))
+}
+
+/// The Solidity type to declare for a parameter: the struct name for tuples and `uint8` for enums,
+/// keeping any array suffix, and the ABI type otherwise.
 fn resolve_type(ty: &str, internal_type: &Option<InternalType>) -> String {
     match internal_type {
-        Some(InternalType::AddressPayable(_)) => "address payable".to_string(),
-        Some(InternalType::Contract(name)) => name.clone(),
-        Some(InternalType::Enum { contract: Some(contract), ty: enum_type }) => {
-            format!("{}.{}", contract, enum_type)
-        }
-        Some(InternalType::Enum { contract: None, ty: enum_type }) => enum_type.clone(),
-        Some(InternalType::Struct { contract: Some(contract), ty: struct_type }) => {
-            format!("{}.{}", contract, struct_type)
+        Some(InternalType::Struct { contract, ty: struct_type }) if ty.starts_with("tuple") => {
+            let (name, suffix) = split_array(struct_type);
+            format!("{}{}", struct_name(contract.as_deref(), name), suffix)
         }
-        Some(InternalType::Struct { contract: None, ty: struct_type }) => struct_type.clone(),
-        Some(InternalType::Other { contract: Some(contract), ty: other_type }) => {
-            format!("{}.{}", contract, other_type)
+        Some(InternalType::Enum { ty: enum_type, .. }) => {
+            format!("uint8{}", split_array(enum_type).1)
         }
-        Some(InternalType::Other { contract: None, ty: other_type }) => other_type.clone(),
-        None => ty.to_string(),
+        Some(InternalType::AddressPayable(_)) => ty.replacen("address", "address payable", 1),
+        _ => ty.to_string(),
     }
 }
 
-pub fn process_events(events: &[Event]) -> (Vec<SolStruct>, Vec<SolEvent>) {
-    let mut structs = HashMap::new();
+/// Name of the generated struct for `contract.name`. Structs defined inside a contract are prefixed
+/// with it (`Market.Order` becomes `Market_Order`), so contracts that each define an `Order` don't
+/// collide.
+fn struct_name(contract: Option<&str>, name: &str) -> String {
+    match contract {
+        Some(contract) => format!("{}_{}", contract, name),
+        None => name.to_string(),
+    }
+}
+
+/// Generates the structs and events for `events`. Structs come out in a stable order with every
+/// struct after the structs it uses.
+///
+/// Also returns a description of every struct that shows up with two different layouts under the
+/// same name. The first layout is kept.
+pub fn process_events(events: &[Event]) -> (Vec<SolStruct>, Vec<SolEvent>, Vec<String>) {
+    let mut structs = Vec::new();
+    let mut conflicts = Vec::new();
     let mut processed_events = Vec::new();
 
     for event in events {
-        collect_structs(&event.inputs, &mut structs);
+        for input in &event.inputs {
+            collect_structs(
+                &input.ty,
+                &input.internal_type,
+                &input.components,
+                &mut structs,
+                &mut conflicts,
+            );
+        }
         let sol_event = SolEvent {
             name: event.name.clone(),
             params: event
                 .inputs
                 .iter()
-                .map(|input| {
-                    let ty = if input.ty == "tuple" {
-                        resolve_type(&input.ty, &input.internal_type)
-                    } else {
-                        input.ty.clone()
-                    };
-                    SolEventParam { name: input.name.clone(), ty, indexed: input.indexed }
+                .enumerate()
+                .map(|(i, input)| SolEventParam {
+                    name: param_name(&input.name, i),
+                    ty: resolve_type(&input.ty, &input.internal_type),
+                    indexed: input.indexed,
                 })
                 .collect(),
+            anonymous: event.anonymous,
         };
         processed_events.push(sol_event);
     }
 
-    (structs.into_values().collect(), processed_events)
+    (structs, processed_events, conflicts)
 }
 
-fn collect_structs(params: &[EventParam], structs: &mut HashMap<String, SolStruct>) {
-    for param in params {
-        if param.ty == "tuple" {
-            if let Some(InternalType::Struct { ty: struct_name, .. }) = &param.internal_type {
-                if !structs.contains_key(struct_name) {
-                    let sol_struct = SolStruct {
-                        name: struct_name.clone(),
-                        fields: param
-                            .components
-                            .iter()
-                            .map(|comp| SolField {
-                                name: comp.name.clone(),
-                                ty: resolve_type(&comp.ty, &comp.internal_type),
-                            })
-                            .collect(),
-                    };
-                    structs.insert(struct_name.clone(), sol_struct);
-                }
+/// Adds the struct behind a `tuple`/`tuple[]` parameter to `structs`, after the structs nested in
+/// its components.
+fn collect_structs(
+    ty: &str,
+    internal_type: &Option<InternalType>,
+    components: &[Param],
+    structs: &mut Vec<SolStruct>,
+    conflicts: &mut Vec<String>,
+) {
+    if !ty.starts_with("tuple") {
+        return;
+    }
+    let Some(InternalType::Struct { contract, ty: struct_type }) = internal_type else {
+        return;
+    };
+
+    for comp in components {
+        collect_structs(&comp.ty, &comp.internal_type, &comp.components, structs, conflicts);
+    }
+    let sol_struct = SolStruct {
+        name: struct_name(contract.as_deref(), split_array(struct_type).0),
+        fields: components
+            .iter()
+            .enumerate()
+            .map(|(i, comp)| SolField {
+                name: param_name(&comp.name, i),
+                ty: resolve_type(&comp.ty, &comp.internal_type),
+            })
+            .collect(),
+    };
+    match structs.iter().find(|s| s.name == sol_struct.name) {
+        None => structs.push(sol_struct),
+        Some(existing) if *existing == sol_struct => {}
+        Some(existing) => {
+            let layout = |s: &SolStruct| {
+                s.fields
+                    .iter()
+                    .map(|f| format!("{} {}", f.ty, f.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let conflict = format!(
+                "struct {} has different layouts: keeping ({}), ignoring ({})",
+                existing.name,
+                layout(existing),
+                layout(&sol_struct)
+            );
+            if !conflicts.contains(&conflict) {
+                conflicts.push(conflict);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(value: serde_json::Value) -> Event {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn names(structs: &[SolStruct]) -> Vec<&str> {
+        structs.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    /// `event PoolsCreated(Pool[] pools, Pool[3] fixed)`, where `Pool` nests a `Range`.
+    fn pools_created() -> Event {
+        let range = json!({
+            "name": "range",
+            "type": "tuple",
+            "internalType": "struct Range",
+            "components": [
+                { "name": "lo", "type": "int24", "internalType": "int24" },
+                { "name": "hi", "type": "int24", "internalType": "int24" }
+            ]
+        });
+        let pool = |name: &str, ty: &str, internal: &str| {
+            json!({
+                "name": name,
+                "type": ty,
+                "internalType": internal,
+                "indexed": false,
+                "components": [
+                    { "name": "id", "type": "uint256", "internalType": "uint256" },
+                    { "name": "kind", "type": "uint8", "internalType": "enum Kind" },
+                    range.clone()
+                ]
+            })
+        };
+        event(json!({
+            "type": "event",
+            "name": "PoolsCreated",
+            "anonymous": false,
+            "inputs": [
+                pool("pools", "tuple[]", "struct Pool[]"),
+                pool("fixed", "tuple[3]", "struct Pool[3]")
+            ]
+        }))
+    }
+
+    #[test]
+    fn declares_nested_structs_first() {
+        let (structs, events, conflicts) = process_events(&[pools_created()]);
+        assert_eq!(names(&structs), ["Range", "Pool"]);
+        assert_eq!(structs[1].fields[1].ty, "uint8");
+        assert_eq!(structs[1].fields[2].ty, "Range");
+        assert!(conflicts.is_empty());
+        assert_eq!(events[0].to_string(), "event PoolsCreated(Pool[] pools, Pool[3] fixed);");
+    }
+
+    #[test]
+    fn keeps_array_suffix_of_enums() {
+        let ev = event(json!({
+            "type": "event",
+            "name": "KindsSet",
+            "anonymous": false,
+            "inputs": [{ "name": "kinds", "type": "uint8[2][]", "internalType": "enum Kind[2][]", "indexed": false }]
+        }));
+        let (structs, events, _) = process_events(&[ev]);
+        assert!(structs.is_empty());
+        assert_eq!(events[0].to_string(), "event KindsSet(uint8[2][] kinds);");
+    }
+
+    #[test]
+    fn splits_array_suffix() {
+        assert_eq!(split_array("Pool[][3]"), ("Pool", "[][3]"));
+        assert_eq!(split_array("Pool"), ("Pool", ""));
+    }
+
+    #[test]
+    fn names_unnamed_and_keyword_params() {
+        assert_eq!(param_name("", 2), "arg2");
+        assert_eq!(param_name("from", 0), "from");
+        assert_eq!(param_name("contract", 0), "contract_");
+        assert_eq!(param_name("address", 0), "address_");
+        assert_eq!(param_name("uint256", 0), "uint256_");
+        assert_eq!(param_name("bytes32", 0), "bytes32_");
+        assert_eq!(param_name("fixed128x18", 0), "fixed128x18_");
+        assert_eq!(param_name("uintFoo", 0), "uintFoo");
+        assert_eq!(param_name("int_", 0), "int_");
+    }
+
+    #[test]
+    fn keeps_anonymous_flag_and_names_params() {
+        let ev = event(json!({
+            "type": "event",
+            "name": "Swap",
+            "anonymous": true,
+            "inputs": [
+                { "name": "", "type": "uint256", "indexed": true },
+                { "name": "contract", "type": "address", "indexed": false }
+            ]
+        }));
+        let (_, events, _) = process_events(&[ev]);
+        assert_eq!(
+            events[0].to_string(),
+            "event Swap(uint256 indexed arg0, address contract_) anonymous;"
+        );
+    }
+
+    #[test]
+    fn picks_first_overload() {
+        let transfer = |inputs: serde_json::Value| {
+            event(
+                json!({ "type": "event", "name": "Transfer", "anonymous": false, "inputs": inputs }),
+            )
+        };
+        let first = transfer(json!([
+            { "name": "to", "type": "address", "indexed": true },
+            { "name": "value", "type": "uint256", "indexed": false }
+        ]));
+        let second = transfer(json!([{ "name": "value", "type": "uint256", "indexed": false }]));
+        let approval =
+            event(json!({ "type": "event", "name": "Approval", "anonymous": false, "inputs": [] }));
+
+        let (kept, dropped) = pick_overloads(vec![first.clone(), approval.clone(), second.clone()]);
+        assert_eq!(kept, [first, approval]);
+        assert_eq!(dropped, [second]);
+    }
+
+    fn order_filled(name: &str, order_fields: serde_json::Value) -> Event {
+        event(json!({
+            "type": "event",
+            "name": name,
+            "anonymous": false,
+            "inputs": [{
+                "name": "order",
+                "type": "tuple",
+                "internalType": "struct Market.Order",
+                "indexed": false,
+                "components": order_fields
+            }, {
+                "name": "other",
+                "type": "tuple",
+                "internalType": "struct Exchange.Order",
+                "indexed": false,
+                "components": [{ "name": "taker", "type": "address", "internalType": "address" }]
+            }]
+        }))
+    }
+
+    #[test]
+    fn prefixes_contract_structs() {
+        let maker =
+            json!([{ "name": "maker", "type": "address", "internalType": "address payable" }]);
+        let (structs, events, conflicts) = process_events(&[order_filled("OrderFilled", maker)]);
+        assert_eq!(names(&structs), ["Market_Order", "Exchange_Order"]);
+        assert_eq!(structs[0].fields[0].ty, "address payable");
+        assert_eq!(
+            events[0].to_string(),
+            "event OrderFilled(Market_Order order, Exchange_Order other);"
+        );
+        assert!(conflicts.is_empty());
+        assert_eq!(struct_name(None, "Order"), "Order");
+    }
+
+    #[test]
+    fn reports_conflicting_layouts_once() {
+        let maker = json!([{ "name": "maker", "type": "address", "internalType": "address" }]);
+        let amount = json!([{ "name": "amount", "type": "uint256", "internalType": "uint256" }]);
+        let events = [
+            order_filled("OrderFilled", maker.clone()),
+            order_filled("OrderCancelled", amount.clone()),
+            order_filled("OrderExpired", amount),
+            order_filled("OrderMatched", maker),
+        ];
+        let (structs, _, conflicts) = process_events(&events);
+        assert_eq!(names(&structs), ["Market_Order", "Exchange_Order"]);
+        assert_eq!(structs[0].fields[0].name, "maker");
+        assert_eq!(
+            conflicts,
+            ["struct Market_Order has different layouts: keeping (address maker), ignoring \
+              (uint256 amount)"]
+        );
+    }
+}
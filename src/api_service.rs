@@ -1,37 +1,97 @@
-use eyre::{eyre, Report};
-use reqwest::Client;
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use eyre::eyre;
+use reqwest::{header::RETRY_AFTER, Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use serde_json::Value;
+use yansi::Paint;
 
+use crate::errors::ApiError;
 use crate::types::{
-    CodegenRequest, CodegenResponse, CompileRequest, CompileResponse, CreateRequest,
-    CreateResponse, DeleteResponse, DeployResponse, ForkRequest, ForkResponse, Graph,
-    GraphDetailsResponse, GraphFile, ListResponse,
+    Account, CodegenRequest, CodegenResponse, CompileRequest, CompileResponse, CreateRequest,
+    CreateResponse, DeleteResponse, DeployResponse, ForkRequest, ForkResponse, GhostVersion, Graph,
+    GraphDetailsResponse, GraphFile, GraphQLRequest, GraphQLResponse, ListResponse, LogsQuery,
+    LogsResponse, PushRequest, PushResponse, SyncStatusResponse, VersionResponse, VersionsResponse,
+    WhoamiResponse,
 };
 
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+#[derive(Copy, Clone, Debug)]
+pub struct HttpOptions {
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Extra attempts for idempotent requests and for 429/503 responses
+    pub retries: u32,
+}
+
 pub struct ApiService {
     client: Client,
     base_url: String,
     api_key: String,
     web_base_url: String,
+    retries: u32,
 }
 
 impl ApiService {
     pub fn new(base_url: String, api_key: String, web_base_url: String) -> Self {
-        Self { client: Client::new(), base_url, api_key, web_base_url }
+        Self { client: Client::new(), base_url, api_key, web_base_url, retries: 0 }
+    }
+
+    pub fn with_options(mut self, options: HttpOptions) -> eyre::Result<Self> {
+        self.client = Client::builder()
+            .timeout(options.timeout)
+            .connect_timeout(options.connect_timeout)
+            .build()?;
+        self.retries = options.retries;
+        Ok(self)
+    }
+
+    /// Builds a service for `api_key`, where `GHOST_BASE_URL`/`GHOST_WEB_BASE_URL` take precedence
+    /// over the given URLs so existing .env setups keep working.
+    ///
+    /// When a profile was selected explicitly, its URLs win instead, since a stray .env must not
+    /// point it at another backend.
+    pub fn with_defaults(
+        api_key: String,
+        base_url: Option<String>,
+        web_url: Option<String>,
+        profile_selected: bool,
+    ) -> Self {
+        let base_url =
+            pick_url("GHOST_BASE_URL", base_url, profile_selected, "https://api.ghostlogs.xyz");
+        let web_base_url =
+            pick_url("GHOST_WEB_BASE_URL", web_url, profile_selected, "https://app.ghostlogs.xyz");
+        Self::new(base_url, api_key, web_base_url)
+    }
+
+    pub fn base_url(&self) -> String {
+        self.base_url.clone()
     }
 
-    pub fn web_base_url(&self) -> String {
-        self.web_base_url.clone()
+    /// Link to a graph version in the web app.
+    pub fn editor_url(&self, id: &str, version_id: &str) -> String {
+        format!("{}/graphs/{}/versions/{}/editor", self.web_base_url, id, version_id)
+    }
+
+    pub async fn whoami(&self) -> eyre::Result<Account> {
+        let url = format!("{}/gg/cli/me", self.base_url);
+        let whoami_res: WhoamiResponse = self.send_idempotent(self.client.get(&url)).await?;
+        if let Some(err) = whoami_res.err {
+            return Err(ApiError::Rejected(err).into());
+        }
+        Ok(whoami_res.account.ok_or_else(|| ApiError::missing("WhoamiResponse", "account"))?)
     }
 
     pub async fn create_graph(&self, req: CreateRequest) -> eyre::Result<CreateResponse> {
         let url = format!("{}/gg/cli/graphs", self.base_url);
-        let response =
-            self.client.post(&url).header("GG-KEY", &self.api_key).json(&req).send().await?;
-
-        let create_resp: CreateResponseInternal = response.json().await?;
-        create_resp.try_into()
+        let create_resp: CreateResponseInternal =
+            self.send(self.client.post(&url).json(&req)).await?;
+        Ok(create_resp.try_into()?)
     }
 
     pub async fn codegen(
@@ -40,12 +100,7 @@ impl ApiService {
         payload: &CodegenRequest,
     ) -> eyre::Result<CodegenResponse> {
         let url = format!("{}/gg/cli/graphs/{}/codegen", self.base_url, id);
-        let response =
-            self.client.post(&url).header("GG-KEY", &self.api_key).json(payload).send().await?;
-        let json: Value = response.json().await?;
-        let codegen_resp: CodegenResponse = serde_json::from_value(json)
-            .map_err(|e| eyre!("Failed to deserialize CodegenResponse: {}", e))?;
-        Ok(codegen_resp)
+        self.send(self.client.post(&url).json(payload)).await
     }
 
     pub async fn compile(
@@ -54,61 +109,203 @@ impl ApiService {
         payload: &CompileRequest,
     ) -> eyre::Result<CompileResponse> {
         let url = format!("{}/gg/cli/graphs/{}/compile", self.base_url, id);
-        let response =
-            self.client.post(&url).header("GG-KEY", &self.api_key).json(payload).send().await?;
-
-        let json: Value = response.json().await?;
-        let compile_res: CompileResponse = serde_json::from_value(json)
-            .map_err(|e| eyre!("Failed to deserialize CompileResponse: {}", e))?;
-        Ok(compile_res)
+        self.send(self.client.post(&url).json(payload)).await
     }
 
     pub async fn deploy(&self, id: &str) -> eyre::Result<DeployResponse> {
         let url = format!("{}/gg/cli/graphs/{}/deploy", self.base_url, id);
-        let response = self.client.post(&url).header("GG-KEY", &self.api_key).send().await?;
+        self.send(self.client.post(&url)).await
+    }
+
+    pub async fn get_sync_status(&self, id: &str) -> eyre::Result<SyncStatusResponse> {
+        let url = format!("{}/gg/cli/graphs/{}/status", self.base_url, id);
+        self.send_idempotent(self.client.get(&url)).await
+    }
 
-        let deploy_res: DeployResponse = serde_json::from_value(response.json().await?)
-            .map_err(|e| eyre!("Failed to deserialize DeployResponse: {}", e))?;
-        Ok(deploy_res)
+    pub async fn get_logs(&self, id: &str, query: &LogsQuery) -> eyre::Result<LogsResponse> {
+        let url = format!("{}/gg/cli/graphs/{}/logs", self.base_url, id);
+        self.send_idempotent(self.client.get(&url).query(query)).await
     }
 
     pub async fn get_graph(&self, id: &str) -> eyre::Result<Graph> {
         let url = format!("{}/gg/cli/graphs/{}", self.base_url, id);
-        let response = self.client.get(&url).header("GG-KEY", &self.api_key).send().await?;
-        let json = response.json().await?;
-        let graph: GraphDetailsResponse = serde_json::from_value(json)
-            .map_err(|e| eyre!("Failed to deserialize Graph response: {}", e))?;
+        let graph: GraphDetailsResponse = self.send_idempotent(self.client.get(&url)).await?;
         Ok(graph.graph)
     }
 
+    pub async fn get_version(&self, id: &str) -> eyre::Result<GhostVersion> {
+        let url = format!("{}/gg/cli/graphs/{}/sources", self.base_url, id);
+        let version_response: VersionResponse = self.send_idempotent(self.client.get(&url)).await?;
+        if let Some(err) = version_response.err {
+            return Err(ApiError::Rejected(err).into());
+        }
+        Ok(version_response
+            .version
+            .ok_or_else(|| ApiError::missing("VersionResponse", "version"))?)
+    }
+
+    pub async fn push_sources(
+        &self,
+        id: &str,
+        payload: &PushRequest,
+    ) -> eyre::Result<PushResponse> {
+        let url = format!("{}/gg/cli/graphs/{}/sources", self.base_url, id);
+        self.send(self.client.put(&url).json(payload)).await
+    }
+
+    pub async fn get_versions(&self, id: &str) -> eyre::Result<VersionsResponse> {
+        let url = format!("{}/gg/cli/graphs/{}/versions", self.base_url, id);
+        self.send_idempotent(self.client.get(&url)).await
+    }
+
+    /// Runs a GraphQL query against a deployed graph's endpoint.
+    ///
+    /// The endpoint is public and its URL comes from the server, so the API key is not sent.
+    pub async fn query_graph(
+        &self,
+        url: &str,
+        payload: &GraphQLRequest,
+    ) -> eyre::Result<GraphQLResponse> {
+        self.send_with_retries(self.client.post(url).json(payload), true).await
+    }
+
     pub async fn get_graphs(&self) -> eyre::Result<ListResponse> {
         let url = format!("{}/gg/cli/list", self.base_url);
-        let response = self.client.get(&url).header("GG-KEY", &self.api_key).send().await?;
-
-        let list_response: ListResponse = serde_json::from_value(response.json().await?)
-            .map_err(|e| eyre!("Failed to deserialize ListResponse: {}", e))?;
-        Ok(list_response)
+        self.send_idempotent(self.client.get(&url)).await
     }
 
     pub async fn fork_graph(&self, id: &str, payload: &ForkRequest) -> eyre::Result<ForkResponse> {
         let url = format!("{}/gg/cli/graphs/{}/fork", self.base_url, id);
-        let response =
-            self.client.post(&url).header("GG-KEY", &self.api_key).json(payload).send().await?;
-
-        let fork_response: ForkResponseInternal = serde_json::from_value(response.json().await?)
-            .map_err(|e| eyre!("Failed to deserialize ForkResponse: {}", e))?;
-        fork_response.try_into()
+        let fork_response: ForkResponseInternal =
+            self.send(self.client.post(&url).json(payload)).await?;
+        Ok(fork_response.try_into()?)
     }
 
     pub async fn delete_graph(&self, id: &str) -> eyre::Result<DeleteResponse> {
         let url = format!("{}/gg/cli/graphs/{}", self.base_url, id);
-        let response = self.client.delete(&url).header("GG-KEY", &self.api_key).send().await?;
+        let delete_response: DeleteResponseInternal = self.send(self.client.delete(&url)).await?;
+        Ok(delete_response.try_into()?)
+    }
+
+    /// Sends a request that must not be repeated, so only 429/503 responses are retried.
+    async fn send<T: DeserializeOwned>(&self, req: RequestBuilder) -> eyre::Result<T> {
+        self.send_with_retries(req.header("GG-KEY", &self.api_key), false).await
+    }
 
-        let delete_response: DeleteResponseInternal =
-            serde_json::from_value(response.json().await?)
-                .map_err(|e| eyre!("Failed to deserialize DeleteResponse: {}", e))?;
-        delete_response.try_into()
+    /// Sends a request that is safe to repeat, so failed connections and 5xx are retried too.
+    async fn send_idempotent<T: DeserializeOwned>(&self, req: RequestBuilder) -> eyre::Result<T> {
+        self.send_with_retries(req.header("GG-KEY", &self.api_key), true).await
     }
+
+    async fn send_with_retries<T: DeserializeOwned>(
+        &self,
+        req: RequestBuilder,
+        idempotent: bool,
+    ) -> eyre::Result<T> {
+        let mut attempt = 0;
+        loop {
+            let res =
+                req.try_clone().ok_or_else(|| eyre!("request cannot be retried"))?.send().await;
+            let delay = match &res {
+                Ok(r)
+                    if matches!(
+                        r.status(),
+                        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+                    ) =>
+                {
+                    Some(retry_after(r).unwrap_or_else(|| backoff(attempt)))
+                }
+                Ok(r) if idempotent && r.status().is_server_error() => Some(backoff(attempt)),
+                Err(e) if idempotent && (e.is_connect() || e.is_timeout()) => {
+                    Some(backoff(attempt))
+                }
+                _ => None,
+            };
+
+            match delay {
+                Some(delay) if attempt < self.retries => {
+                    attempt += 1;
+                    let reason = match &res {
+                        Ok(r) => r.status().to_string(),
+                        Err(e) => e.to_string(),
+                    };
+                    eprintln!(
+                        "Request failed ({}), retrying in {:.1}s ({}/{})",
+                        reason,
+                        delay.as_secs_f64(),
+                        attempt,
+                        self.retries
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                _ => return Ok(parse_response(res?).await?),
+            }
+        }
+    }
+}
+
+fn pick_url(var: &str, configured: Option<String>, configured_wins: bool, default: &str) -> String {
+    match (env::var(var).ok().filter(|v| !v.trim().is_empty()), configured) {
+        (Some(from_env), Some(configured)) if configured_wins => {
+            if from_env != configured {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Warning: ignoring {}={}, the selected profile uses {}",
+                        var, from_env, configured
+                    )
+                    .yellow()
+                );
+            }
+            configured
+        }
+        (Some(from_env), _) => from_env,
+        (None, configured) => configured.unwrap_or_else(|| default.to_string()),
+    }
+}
+
+/// Exponential backoff with jitter: a random delay between half and all of `base * 2^attempt`.
+fn backoff(attempt: u32) -> Duration {
+    let max = BACKOFF_BASE.saturating_mul(2u32.saturating_pow(attempt)).min(BACKOFF_MAX);
+    let random = RandomState::new().build_hasher().finish();
+    let jitter = (random % 1000) as f64 / 1000.0;
+    max.mul_f64(0.5 + jitter / 2.0)
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    let secs: u64 = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs).min(BACKOFF_MAX))
+}
+
+/// Maps error statuses to [`ApiError`] and deserializes everything else as `T`.
+///
+/// Other 4xx statuses are still deserialized since the API reports codegen/compile failures in
+/// the body's `err` field.
+async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, ApiError> {
+    let status = response.status();
+    let url = response.url().to_string();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok());
+    let body = response.text().await.map_err(|e| ApiError::MalformedResponse {
+        reason: e.to_string(),
+        payload: String::new(),
+    })?;
+
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => return Err(ApiError::Unauthorized),
+        StatusCode::NOT_FOUND => return Err(ApiError::NotFound { url }),
+        StatusCode::TOO_MANY_REQUESTS => return Err(ApiError::RateLimited { retry_after }),
+        s if s.is_server_error() => {
+            return Err(ApiError::Server { status: s.as_u16(), body: ApiError::snippet(&body) })
+        }
+        _ => {}
+    }
+
+    serde_json::from_str(&body)
+        .map_err(|e| ApiError::MalformedResponse { reason: e.to_string(), payload: body })
 }
 
 #[derive(Debug, Deserialize)]
@@ -118,12 +315,12 @@ struct DeleteResponseInternal {
 }
 
 impl TryInto<DeleteResponse> for DeleteResponseInternal {
-    type Error = Report;
+    type Error = ApiError;
 
-    fn try_into(self) -> eyre::Result<DeleteResponse> {
+    fn try_into(self) -> Result<DeleteResponse, ApiError> {
         match self {
             DeleteResponseInternal { ok: true } => Ok(DeleteResponse {}),
-            _ => Err(eyre!("Unexpected API response")),
+            _ => Err(ApiError::unexpected(&self)),
         }
     }
 }
@@ -138,9 +335,9 @@ struct CreateResponseInternal {
 }
 
 impl TryInto<CreateResponse> for CreateResponseInternal {
-    type Error = Report;
+    type Error = ApiError;
 
-    fn try_into(self) -> eyre::Result<CreateResponse> {
+    fn try_into(self) -> Result<CreateResponse, ApiError> {
         match self {
             CreateResponseInternal {
                 ok: true,
@@ -148,7 +345,7 @@ impl TryInto<CreateResponse> for CreateResponseInternal {
                 version_id: Some(version_id),
                 sources: Some(sources),
             } => Ok(CreateResponse { id, version_id, sources }),
-            _ => Err(eyre!("Unexpected API response")),
+            other => Err(ApiError::unexpected(&other)),
         }
     }
 }
@@ -163,9 +360,9 @@ struct ForkResponseInternal {
 }
 
 impl TryInto<ForkResponse> for ForkResponseInternal {
-    type Error = Report;
+    type Error = ApiError;
 
-    fn try_into(self) -> eyre::Result<ForkResponse> {
+    fn try_into(self) -> Result<ForkResponse, ApiError> {
         match self {
             ForkResponseInternal {
                 ok: true,
@@ -173,7 +370,7 @@ impl TryInto<ForkResponse> for ForkResponseInternal {
                 ghost_graph_version_id: Some(version_id),
                 sources: Some(sources),
             } => Ok(ForkResponse { id, version_id, sources }),
-            _ => Err(eyre!("Unexpected API response")),
+            other => Err(ApiError::unexpected(&other)),
         }
     }
 }
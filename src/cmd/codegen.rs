@@ -3,18 +3,26 @@ use std::path::PathBuf;
 
 use clap::Parser;
 use eyre::eyre;
+use serde_json::json;
 
 use crate::api_service::ApiService;
+use crate::output::{self, say};
 use crate::types::CodegenRequest;
-use crate::utils::{check_and_get_conf, write_files};
+use crate::utils::{check_and_get_conf, write_synced_files};
 
 #[derive(Clone, Debug, Default, Parser)]
 pub struct CodegenCmd {}
 
 impl CodegenCmd {
     pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
-        let config = check_and_get_conf(&["src/schema.sol", "src/events.sol"], api).await?;
-        println!("Running codegen for id={}", config.version_id);
+        let (version_id, files) = self.generate(api).await?;
+        output::emit(&json!({ "versionId": version_id, "files": files }))
+    }
+
+    /// Runs codegen and saves the generated sources, returning the version id and their paths.
+    pub async fn generate(&self, api: &ApiService) -> eyre::Result<(String, Vec<String>)> {
+        let mut config = check_and_get_conf(&["src/schema.sol", "src/events.sol"], api).await?;
+        say!("Running codegen for id={}", config.version_id);
 
         let payload = CodegenRequest {
             schema_code: fs::read_to_string("src/schema.sol")?,
@@ -24,12 +32,12 @@ impl CodegenCmd {
         if let Some(err) = resp.err {
             return Err(eyre!(err));
         }
+        let mut files = Vec::new();
         if let Some(version) = resp.version {
-            write_files(&PathBuf::from("."), version.sources)?;
-            println!(
-                "All files saved. Go ahead and modify indexer.sol and then run `ghost compile`"
-            )
+            files = version.sources.iter().map(|s| s.path.clone()).collect();
+            write_synced_files(&PathBuf::from("."), &mut config, version.sources)?;
+            say!("All files saved. Go ahead and modify indexer.sol and then run `ghost compile`")
         }
-        Ok(())
+        Ok((config.version_id, files))
     }
 }
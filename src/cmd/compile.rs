@@ -3,28 +3,32 @@ use std::path::PathBuf;
 
 use clap::Parser;
 use eyre::eyre;
+use serde_json::json;
 
 use crate::api_service::ApiService;
+use crate::output::{self, say};
 use crate::types::CompileRequest;
-use crate::utils::{check_and_get_conf, write_files};
+use crate::utils::{check_and_get_conf, write_synced_files};
 
 #[derive(Clone, Debug, Default, Parser)]
 pub struct CompileCmd {}
 
 impl CompileCmd {
     pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
-        let config = check_and_get_conf(&["src/indexer.sol"], api).await?;
-        println!("Running compile for id={}", config.version_id);
+        let mut config = check_and_get_conf(&["src/indexer.sol"], api).await?;
+        say!("Running compile for id={}", config.version_id);
 
         let payload = CompileRequest { indexer_code: fs::read_to_string("src/indexer.sol")? };
         let resp = api.compile(&config.version_id, &payload).await?;
         if let Some(err) = resp.err {
             return Err(eyre!(err));
         }
+        let mut files = Vec::new();
         if let Some(version) = resp.version {
-            write_files(&PathBuf::from("."), version.sources)?;
-            println!("Successfully compiled. Go ahead and run `ghost deploy` to deploy the graph")
+            files = version.sources.iter().map(|s| s.path.clone()).collect();
+            write_synced_files(&PathBuf::from("."), &mut config, version.sources)?;
+            say!("Successfully compiled. Go ahead and run `ghost deploy` to deploy the graph")
         }
-        Ok(())
+        output::emit(&json!({ "versionId": config.version_id, "files": files }))
     }
 }
@@ -0,0 +1,57 @@
+use clap::Parser;
+use eyre::WrapErr;
+use serde_json::json;
+
+use crate::api_service::{ApiService, HttpOptions};
+use crate::configure::{list_profiles, set_api_key};
+use crate::output::{self, say};
+use crate::secrets::SecretBackend;
+
+#[derive(Clone, Debug, Parser)]
+pub struct ConfigureCmd {
+    #[arg(help = "Set the Ghost API key")]
+    pub api_key: String,
+
+    /// Ghost API base URL for this profile
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Ghost web app URL for this profile
+    #[arg(long)]
+    pub web_url: Option<String>,
+
+    /// Where to store the key (defaults to the OS keyring when available)
+    #[arg(long, value_enum)]
+    pub secret_backend: Option<SecretBackend>,
+
+    /// Save the key without checking it against the Ghost API
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+impl ConfigureCmd {
+    pub async fn run(self, profile: Option<&str>, http: HttpOptions) -> eyre::Result<()> {
+        let account = if self.no_verify {
+            None
+        } else {
+            let (active, profiles) = list_profiles()?;
+            let existing = profiles.get(profile.unwrap_or(&active));
+            let api = ApiService::with_defaults(
+                self.api_key.clone(),
+                self.base_url.clone().or_else(|| existing.and_then(|p| p.base_url.clone())),
+                self.web_url.clone().or_else(|| existing.and_then(|p| p.web_url.clone())),
+                profile.is_some(),
+            )
+            .with_options(http)?;
+            let account = api
+                .whoami()
+                .await
+                .wrap_err("Could not verify the API key. Use --no-verify to save it anyway")?;
+            say!("Verified API key for {}", account);
+            Some(account)
+        };
+
+        set_api_key(profile, &self.api_key, self.base_url, self.web_url, self.secret_backend)?;
+        output::emit(&json!({ "configured": true, "account": account }))
+    }
+}
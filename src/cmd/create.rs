@@ -1,8 +1,10 @@
 use std::path::PathBuf;
 
 use clap::{Parser, ValueHint};
+use serde_json::json;
 
 use crate::api_service::ApiService;
+use crate::output::{self, say};
 use crate::types::{Chain, CreateRequest};
 use crate::utils::{check_and_create_dir, write_sources_and_conf};
 
@@ -37,17 +39,14 @@ impl CreateCmd {
         });
         let payload = CreateRequest { chain: chain.chain_id(), name };
         let resp = api.create_graph(payload).await?;
-        println!("Success! Created a new graph");
-        println!(
-            "View online at {}/graphs/{}/versions/{}/editor",
-            api.web_base_url(),
-            resp.id,
-            resp.version_id
-        );
-        println!("\nInitializing files...");
+        let url = api.editor_url(&resp.id, &resp.version_id);
+        say!("Success! Created a new graph");
+        say!("View online at {}", url);
+        say!("\nInitializing files...");
+        let doc = json!({ "id": resp.id, "versionId": resp.version_id, "dir": dir, "url": url });
         write_sources_and_conf(&dir, resp.id, resp.version_id, Some(chain), resp.sources)?;
-        println!("done! Check the {:?} directory", dir);
+        say!("done! Check the {:?} directory", dir);
 
-        Ok(())
+        output::emit(&doc)
     }
 }
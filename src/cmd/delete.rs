@@ -1,6 +1,8 @@
 use clap::Parser;
+use serde_json::json;
 
 use crate::api_service::ApiService;
+use crate::output::{self, say};
 
 #[derive(Clone, Debug, Default, Parser)]
 pub struct DeleteCmd {
@@ -11,9 +13,9 @@ pub struct DeleteCmd {
 
 impl DeleteCmd {
     pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
-        println!("Deleting graph with ID: {}", self.id);
+        say!("Deleting graph with ID: {}", self.id);
         api.delete_graph(&self.id).await?;
-        println!("Successfully deleted the graph");
-        Ok(())
+        say!("Successfully deleted the graph");
+        output::emit(&json!({ "id": self.id, "deleted": true }))
     }
 }
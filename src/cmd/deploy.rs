@@ -1,30 +1,72 @@
+use std::time::Duration;
+
 use clap::Parser;
 use eyre::eyre;
+use serde_json::json;
 
 use crate::api_service::ApiService;
-use crate::utils::check_and_get_conf;
+use crate::cmd::{check_sync, wait_for_sync};
+use crate::output::{self, say};
+use crate::utils::{check_and_get_conf, confirm};
 
 #[derive(Clone, Debug, Default, Parser)]
-pub struct DeployCmd {}
+pub struct DeployCmd {
+    /// Deploy this version instead of the one in config.json
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Don't ask for confirmation when deploying another version
+    #[arg(long, short)]
+    pub yes: bool,
+
+    /// Wait until the graph has indexed up to the chain head
+    #[arg(long, short)]
+    pub wait: bool,
+
+    /// Give up waiting for indexing after this many seconds
+    #[arg(long, requires = "wait")]
+    pub wait_timeout: Option<u64>,
+}
 
 impl DeployCmd {
     pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
         let config = check_and_get_conf(&[], api).await?;
-        println!("Running deploy for id={}", config.version_id);
+        let version_id = match self.version {
+            Some(version_id) if version_id != config.version_id => {
+                if !confirm(&format!("Deploy version {} of the graph?", version_id), self.yes)? {
+                    eyre::bail!("Deploy cancelled");
+                }
+                version_id
+            }
+            _ => config.version_id,
+        };
+        say!("Running deploy for id={}", version_id);
 
-        let resp = api.deploy(&config.version_id).await?;
+        let resp = api.deploy(&version_id).await?;
         if let Some(err) = resp.err {
             return Err(eyre!(err));
         }
+        let url = api.editor_url(&config.id, &version_id);
         if let Some(_ok) = resp.ok {
-            println!("Successfully deployed. \n");
-            println!(
-                "View online at {}/graphs/{}/versions/{}/editor",
-                api.web_base_url(),
-                config.id,
-                config.version_id
-            );
+            say!("Successfully deployed. \n");
+            say!("View online at {}", url);
+        }
+
+        let sync = if self.wait && resp.ok.is_some() {
+            say!("\nWaiting for indexing to catch up...");
+            Some(wait_for_sync(api, &version_id, Duration::from_secs(5), self.wait_timeout).await?)
+        } else {
+            None
+        };
+        if let Some(sync) = &sync {
+            check_sync(sync)?;
         }
-        Ok(())
+        output::emit(&json!({
+            "id": config.id,
+            "versionId": version_id,
+            "deployed": resp.ok.unwrap_or_default(),
+            "url": url,
+            "sync": sync,
+        }))
     }
 }
@@ -1,46 +1,462 @@
-use crate::abi_processor::process_events;
+use crate::abi_processor::{pick_overloads, process_events, SolEvent, SolStruct};
 use crate::api_service::ApiService;
-use crate::etherscan_client::EtherscanClient;
+use crate::cmd::CodegenCmd;
+use crate::etherscan_client::{ContractAbi, EtherscanClient};
+use crate::events_file;
+use crate::output::{self, say};
 use crate::utils::check_and_get_conf;
-use alloy_json_abi::Event;
-use alloy_primitives::Address;
-use clap::Parser;
-use eyre::OptionExt;
+use alloy_json_abi::{Event, JsonAbi};
+use alloy_primitives::{Address, B256};
+use clap::{Parser, ValueEnum};
+use dialoguer::MultiSelect;
+use eyre::{eyre, OptionExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use yansi::Paint;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum EventsFormat {
+    /// Solidity structs and an events block, as used in events.sol
+    #[default]
+    Sol,
+    /// JSON ABI fragment
+    AbiJson,
+    /// Human-readable signatures, one per line
+    HumanReadable,
+}
 
 #[derive(Clone, Debug, Default, Parser)]
+#[command(group = clap::ArgGroup::new("source").required(true).args(["address", "abi", "artifact"]))]
 pub struct EventsCommand {
-    #[arg(long, short, env = "ETHERSCAN_API_KEY", help = "etherscan key for the target chain")]
-    pub api_key: String,
+    #[arg(long, env = "ETHERSCAN_API_KEY", help = "etherscan key for the target chain")]
+    pub api_key: Option<String>,
+
+    /// Fetch the ABI of this verified contract from the block explorer
+    #[arg(long, short)]
+    pub address: Option<Address>,
+
+    /// Merge the events of a proxy and every implementation it points to
+    #[arg(long, conflicts_with_all = ["abi", "artifact"])]
+    pub merge_proxy: bool,
+
+    /// Read the ABI from a JSON file
+    #[arg(long)]
+    pub abi: Option<PathBuf>,
+
+    /// Read the ABI from a Foundry (`out/Foo.sol/Foo.json`) or Hardhat build artifact
+    #[arg(long)]
+    pub artifact: Option<PathBuf>,
+
+    /// Add the events and structs to src/events.sol instead of printing them
+    #[arg(long, short)]
+    pub write: bool,
+
+    /// Run codegen after writing src/events.sol
+    #[arg(long, requires = "write")]
+    pub codegen: bool,
+
+    /// Pick the events to keep interactively
+    #[arg(long, short, conflicts_with = "only")]
+    pub select: bool,
 
+    /// Only keep these events, by name or signature (e.g. Transfer,Swap)
+    #[arg(long)]
+    pub only: Vec<String>,
+
+    /// Drop these events, by name or signature
+    #[arg(long)]
+    pub exclude: Vec<String>,
+
+    /// Annotate each event with its canonical signature and topic0. Printed to stderr for formats
+    /// other than `sol`
     #[arg(long, short)]
-    pub address: Address,
+    pub topics: bool,
+
+    /// How to print the events
+    #[arg(long, value_enum, default_value_t)]
+    pub format: EventsFormat,
+
+    /// Save the output to this file instead of printing it
+    #[arg(long, short, conflicts_with = "write")]
+    pub out: Option<PathBuf>,
 }
 
 impl EventsCommand {
     pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
-        let config = check_and_get_conf(&[], api).await?;
-        let chain = config.chain.ok_or_eyre("no chain found")?;
-        env::set_var("ETHERSCAN_API_KEY", self.api_key);
-        let client = EtherscanClient::new(chain)?;
-        let abi = client.fetch_abi(self.address).await?;
+        let (source, abi, origins) = self.load_abi(api).await?;
         let ev: Vec<Event> =
             abi.events.values().flat_map(|events| events.iter().cloned()).collect();
+        let (ev, overloads) = pick_overloads(self.filter_events(ev)?);
+        for event in &overloads {
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: skipping overload {} of {}. Use --only '{}' to pick it instead",
+                    event.signature(),
+                    event.name,
+                    self.only_with(event)
+                )
+                .yellow()
+            );
+        }
         if ev.is_empty() {
-            println!("No events found for {}", self.address);
-            return Ok(());
+            say!("No events found in {}", source);
+            return output::emit(&json!({ "source": source, "structs": [], "events": [] }));
+        }
+
+        let (structs, events, conflicts) = process_events(&ev);
+        for conflict in &conflicts {
+            eprintln!("{}", format!("Warning: {}", conflict).yellow());
+        }
+        if self.write {
+            if self.format != EventsFormat::Sol {
+                eyre::bail!("--write only supports --format sol");
+            }
+            report_origins(&ev, &origins);
+            let origins = origins_json(&ev, &origins);
+            return self.write_events(api, &source, &structs, &events, &conflicts, origins).await;
         }
 
-        let res = process_events(&ev);
-        for s in res.0 {
-            println!("{}", s);
+        let content = match self.format {
+            EventsFormat::Sol => self.render_sol(&ev, &structs, &events, &origins),
+            EventsFormat::AbiJson => {
+                let mut abi = JsonAbi::new();
+                for event in &ev {
+                    abi.events.entry(event.name.clone()).or_default().push(event.clone());
+                }
+                serde_json::to_string_pretty(&abi)?
+            }
+            EventsFormat::HumanReadable => {
+                ev.iter().map(human_readable).collect::<Vec<_>>().join("\n")
+            }
+        };
+        if self.format != EventsFormat::Sol {
+            report_origins(&ev, &origins);
+            if self.topics {
+                report_topics(&ev);
+            }
         }
-        println!("events {{");
-        for ev in res.1 {
-            println!("\t{}", ev);
+        match &self.out {
+            Some(out) => {
+                fs::write(out, format!("{}\n", content))?;
+                say!("Wrote {} event(s) to {}", ev.len(), out.display());
+            }
+            None => {
+                say!("{}", content);
+                if self.format == EventsFormat::Sol {
+                    say!("\n\nYou can copy the relevant events into your events.sol file");
+                }
+            }
         }
-        println!("}}");
-        println!("\n\nYou can copy the relevant events into your events.sol file");
-        Ok(())
+        output::emit(&json!({
+            "source": source,
+            "content": content,
+            "out": self.out,
+            "structs": structs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "events": events.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "structConflicts": conflicts,
+            "topics": self.topics.then(|| ev.iter().map(topic_json).collect::<Vec<_>>()),
+            "origins": origins_json(&ev, &origins),
+        }))
     }
+
+    fn render_sol(
+        &self,
+        abi_events: &[Event],
+        structs: &[SolStruct],
+        events: &[SolEvent],
+        origins: &HashMap<B256, Origin>,
+    ) -> String {
+        let mut lines: Vec<String> = structs.iter().map(ToString::to_string).collect();
+        lines.push("events {".to_string());
+        for (abi_event, event) in abi_events.iter().zip(events) {
+            if let Some(origin) = origins.get(&abi_event.selector()) {
+                lines.push(format!("\t// from {}", origin));
+            }
+            if self.topics {
+                lines.push(format!("\t// {}", topic_comment(abi_event)));
+            }
+            lines.push(format!("\t{}", event));
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    async fn write_events(
+        &self,
+        api: &ApiService,
+        source: &str,
+        structs: &[SolStruct],
+        events: &[SolEvent],
+        struct_conflicts: &[String],
+        origins: Option<Vec<Value>>,
+    ) -> eyre::Result<()> {
+        check_and_get_conf(&["src/events.sol"], api).await?;
+        let path = Path::new("src/events.sol");
+        let report = events_file::merge(&fs::read_to_string(path)?, structs, events)
+            .map_err(|e| eyre!("Not updating src/events.sol: {}", e))?;
+        fs::write(path, &report.content)?;
+
+        for added in &report.added {
+            say!("{} {}", "added:   ".green(), added);
+        }
+        for skipped in &report.skipped {
+            say!("{} {}", "exists:  ".dim(), skipped);
+        }
+        for conflict in &report.conflicts {
+            say!(
+                "{} {} is already declared as {}",
+                "conflict:".red(),
+                conflict.generated,
+                conflict.existing
+            );
+        }
+        say!(
+            "\nUpdated src/events.sol: {} added, {} already present, {} conflict(s)",
+            report.added.len(),
+            report.skipped.len(),
+            report.conflicts.len()
+        );
+
+        let codegen = if self.codegen && !report.added.is_empty() {
+            say!("");
+            Some(CodegenCmd::default().generate(api).await?.1)
+        } else {
+            None
+        };
+        let conflicts: Vec<_> = report
+            .conflicts
+            .iter()
+            .map(|c| json!({ "name": c.name, "existing": c.existing, "generated": c.generated }))
+            .collect();
+        output::emit(&json!({
+            "source": source,
+            "added": report.added,
+            "skipped": report.skipped,
+            "conflicts": conflicts,
+            "structConflicts": struct_conflicts,
+            "origins": origins,
+            "codegen": codegen,
+        }))
+    }
+
+    /// The `--only` value that keeps the current selection but swaps in `overload` for the other
+    /// events of its name.
+    fn only_with(&self, overload: &Event) -> String {
+        let mut patterns = split_patterns(&self.only);
+        patterns.retain(|p| {
+            let p: String = p.split_whitespace().collect();
+            p != overload.name && !p.starts_with(&format!("{}(", overload.name))
+        });
+        patterns.push(overload.signature());
+        patterns.join(",")
+    }
+
+    /// Applies `--only`, `--exclude` and `--select`, in that order.
+    fn filter_events(&self, mut events: Vec<Event>) -> eyre::Result<Vec<Event>> {
+        let only = split_patterns(&self.only);
+        let exclude = split_patterns(&self.exclude);
+        for pattern in &only {
+            if !events.iter().any(|e| matches_event(e, pattern)) {
+                eyre::bail!("No event matches `{}`", pattern);
+            }
+        }
+        if !only.is_empty() {
+            events.retain(|e| only.iter().any(|p| matches_event(e, p)));
+        }
+        events.retain(|e| !exclude.iter().any(|p| matches_event(e, p)));
+
+        if self.select && !events.is_empty() {
+            if output::is_json() || !std::io::stdin().is_terminal() {
+                eyre::bail!("--select needs an interactive terminal, use --only instead");
+            }
+            let labels: Vec<_> = events.iter().map(|e| e.signature()).collect();
+            let chosen = MultiSelect::new()
+                .with_prompt("Select events (space to toggle, enter to confirm)")
+                .items(&labels)
+                .interact()?;
+            events = chosen.into_iter().map(|i| events[i].clone()).collect();
+        }
+        Ok(events)
+    }
+
+    /// Loads the ABI from whichever source was given, along with a description of that source.
+    ///
+    /// With `--merge-proxy`, also returns the contract each event was taken from.
+    async fn load_abi(
+        &self,
+        api: &ApiService,
+    ) -> eyre::Result<(String, JsonAbi, HashMap<B256, Origin>)> {
+        if let Some(path) = &self.abi {
+            return Ok((path.display().to_string(), read_abi_file(path, false)?, HashMap::new()));
+        }
+        if let Some(path) = &self.artifact {
+            return Ok((path.display().to_string(), read_abi_file(path, true)?, HashMap::new()));
+        }
+
+        let address =
+            self.address.ok_or_eyre("one of --address, --abi or --artifact is required")?;
+        let config = check_and_get_conf(&[], api).await?;
+        let chain = config.chain.ok_or_eyre("no chain found")?;
+        let api_key = self
+            .api_key
+            .clone()
+            .ok_or_eyre("--api-key or ETHERSCAN_API_KEY is required to fetch ABIs by address")?;
+        env::set_var("ETHERSCAN_API_KEY", api_key);
+        let client = EtherscanClient::new(chain)?;
+        if self.merge_proxy {
+            let (abi, origins) = merge_abis(client.fetch_abi_chain(address).await?);
+            return Ok((address.to_string(), abi, origins));
+        }
+        Ok((address.to_string(), client.fetch_abi(address).await?, HashMap::new()))
+    }
+}
+
+/// The contract in a proxy chain that declared an event.
+#[derive(Clone, Debug)]
+struct Origin {
+    name: String,
+    address: Address,
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.address)
+    }
+}
+
+/// Combines the events of every contract in a proxy chain, keeping the first contract that
+/// declares each selector.
+fn merge_abis(chain: Vec<ContractAbi>) -> (JsonAbi, HashMap<B256, Origin>) {
+    let mut merged = JsonAbi::new();
+    let mut origins = HashMap::new();
+    for contract in chain {
+        let origin = Origin { name: contract.name, address: contract.address };
+        for event in contract.abi.events.into_values().flatten() {
+            if origins.contains_key(&event.selector()) {
+                continue;
+            }
+            origins.insert(event.selector(), origin.clone());
+            merged.events.entry(event.name.clone()).or_default().push(event);
+        }
+    }
+    (merged, origins)
+}
+
+/// Lists where each event came from on stderr, for outputs that can't carry comments.
+fn report_origins(events: &[Event], origins: &HashMap<B256, Origin>) {
+    if output::is_json() {
+        return;
+    }
+    for event in events {
+        if let Some(origin) = origins.get(&event.selector()) {
+            eprintln!("{}", format!("{} from {}", event.signature(), origin).dim());
+        }
+    }
+}
+
+/// Lists the topics on stderr for formats that have no room for comments.
+fn report_topics(events: &[Event]) {
+    if output::is_json() {
+        return;
+    }
+    for event in events {
+        eprintln!("{}", topic_comment(event).dim());
+    }
+}
+
+fn origins_json(events: &[Event], origins: &HashMap<B256, Origin>) -> Option<Vec<Value>> {
+    (!origins.is_empty()).then(|| {
+        events
+            .iter()
+            .filter_map(|e| {
+                let origin = origins.get(&e.selector())?;
+                Some(json!({
+                    "event": e.signature(),
+                    "contract": origin.name,
+                    "address": origin.address,
+                }))
+            })
+            .collect()
+    })
+}
+
+/// The event as a human-readable ABI signature, e.g. `event Transfer(address indexed from, ...)`.
+fn human_readable(event: &Event) -> String {
+    let anonymous = if event.anonymous { " anonymous" } else { "" };
+    format!("{}{}", event.full_signature(), anonymous)
+}
+
+fn topic_json(event: &Event) -> Value {
+    json!({
+        "name": event.name,
+        "signature": event.signature(),
+        "topic0": (!event.anonymous).then(|| event.selector()),
+    })
+}
+
+fn topic_comment(event: &Event) -> String {
+    if event.anonymous {
+        format!("{} (anonymous, no topic0)", event.signature())
+    } else {
+        format!("{} {}", event.signature(), event.selector())
+    }
+}
+
+/// Whether `pattern` is the event's name or its signature, like `Transfer(address,address,uint256)`.
+/// Splits `Transfer,Swap(address,uint256)` on the commas outside parentheses, so signatures
+/// survive intact.
+fn split_patterns(values: &[String]) -> Vec<String> {
+    let mut patterns = Vec::new();
+    for value in values {
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (i, c) in value.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    patterns.push(value[start..i].trim().to_string());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        patterns.push(value[start..].trim().to_string());
+    }
+    patterns.retain(|p| !p.is_empty());
+    patterns
+}
+
+fn matches_event(event: &Event, pattern: &str) -> bool {
+    let pattern: String = pattern.split_whitespace().collect();
+    event.name == pattern || event.signature() == pattern
+}
+
+/// Reads a raw ABI array, or the `abi` field of a Foundry/Hardhat artifact.
+fn read_abi_file(path: &Path, artifact: bool) -> eyre::Result<JsonAbi> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| eyre!("cannot read {}: {}", path.display(), e))?;
+    let json: Value = serde_json::from_str(&contents)
+        .map_err(|e| eyre!("{} is not valid JSON: {}", path.display(), e))?;
+    let abi = match json {
+        Value::Object(mut artifact) if artifact.contains_key("abi") => {
+            artifact.remove("abi").unwrap()
+        }
+        abi @ Value::Array(_) if !artifact => abi,
+        _ => eyre::bail!(
+            "{} is not {}",
+            path.display(),
+            if artifact {
+                "a build artifact with an `abi` field"
+            } else {
+                "an ABI array or artifact"
+            }
+        ),
+    };
+    serde_json::from_value(abi).map_err(|e| eyre!("invalid ABI in {}: {}", path.display(), e))
 }
@@ -1,9 +1,12 @@
 use std::path::PathBuf;
 
 use clap::{Parser, ValueHint};
-use eyre::{eyre, OptionExt};
+use eyre::OptionExt;
+use serde_json::json;
 
 use crate::api_service::ApiService;
+use crate::errors::GhostError;
+use crate::output::{self, say};
 use crate::types::{ForkRequest, GraphConfig};
 use crate::utils::{check_and_create_dir, write_sources_and_conf};
 
@@ -38,7 +41,7 @@ impl ForkCmd {
     pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
         let (dir, id) = if self.replace {
             let conf = GraphConfig::read(PathBuf::from("config.json"))
-                .map_err(|_| eyre!("cannot read config.json. Must be in a Ghost directory"))?;
+                .map_err(|_| GhostError::NotGhostDir)?;
             (PathBuf::from("."), self.id.or(Some(conf.version_id)))
         } else {
             check_and_create_dir(&self.dir)?;
@@ -46,23 +49,30 @@ impl ForkCmd {
         };
 
         let id = id.ok_or_eyre("must pass --id if not --replace")?;
-        println!("Forking graph with ID: {}", id);
+        say!("Forking graph with ID: {}", id);
         let name = self
             .name
             .or_else(|| dir.file_name().and_then(|os_str| os_str.to_str()).map(String::from));
 
         let resp = api.fork_graph(&id, &ForkRequest { name }).await?;
-        println!("Graph has been successfully forked. Setting up local files...");
+        say!("Graph has been successfully forked. Setting up local files...");
 
+        let doc = json!({
+            "id": resp.id,
+            "versionId": resp.version_id,
+            "forkedFrom": id,
+            "dir": dir,
+            "deletedOld": self.delete,
+        });
         write_sources_and_conf(&dir, resp.id, resp.version_id, None, resp.sources)?;
-        println!("done! Check the {:?} directory", dir);
+        say!("done! Check the {:?} directory", dir);
 
         if self.delete {
-            println!("Deleting the old graph id: {:?}", id);
+            say!("Deleting the old graph id: {:?}", id);
             api.delete_graph(&id).await?;
-            println!("Successfully deleted the old graph");
+            say!("Successfully deleted the old graph");
         }
 
-        Ok(())
+        output::emit(&doc)
     }
 }
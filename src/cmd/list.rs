@@ -1,11 +1,13 @@
-use chrono::{DateTime, Local};
 use clap::Parser;
-use eyre::eyre;
+use serde_json::json;
 use std::collections::HashMap;
 use toolstr::{Color, ColumnFormatShorthand, Table, TableFormat};
 
 use crate::api_service::ApiService;
 use crate::constants::CHAIN_NAMES;
+use crate::errors::ApiError;
+use crate::output::{self, say};
+use crate::utils::fmt_time;
 
 const DEFAULT_COLOR_TITLE: Color = Color::TrueColor { r: 206, g: 147, b: 249 };
 const DEFAULT_COLOR_COMMENT: Color = Color::TrueColor { r: 98, g: 114, b: 164 };
@@ -15,16 +17,20 @@ pub struct ListCmd {}
 
 impl ListCmd {
     pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
-        println!("Fetching list of graphs...");
+        say!("Fetching list of graphs...");
 
         let resp = api.get_graphs().await?;
 
         if let Some(err) = resp.err {
-            return Err(eyre!(err));
+            return Err(ApiError::Rejected(err).into());
+        }
+
+        if output::is_json() {
+            return output::emit(&json!({ "graphs": resp.graphs }));
         }
 
         if resp.graphs.is_empty() {
-            println!("No graphs found.");
+            say!("No graphs found.");
             return Ok(());
         }
 
@@ -76,9 +82,3 @@ impl ListCmd {
         Ok(())
     }
 }
-
-fn fmt_time(dt: &str) -> Option<String> {
-    let utc_time = DateTime::parse_from_rfc3339(dt).ok()?;
-    let local_time = utc_time.with_timezone(&Local);
-    Some(local_time.format("%Y-%m-%d %H:%M").to_string())
-}
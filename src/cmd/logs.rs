@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use clap::Parser;
+use serde_json::json;
+use yansi::Paint;
+
+use crate::api_service::ApiService;
+use crate::errors::ApiError;
+use crate::output::{self, say};
+use crate::types::{LogEntry, LogLevel, LogsQuery};
+use crate::utils::check_and_get_conf;
+
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct LogsCmd {
+    /// Keep polling for new log lines
+    #[arg(long, short)]
+    pub follow: bool,
+
+    /// Only show logs newer than this, either a duration (30s, 10m, 2h, 1d) or an RFC 3339 time
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only show logs at this level or above
+    #[arg(long, value_enum)]
+    pub level: Option<LogLevel>,
+}
+
+impl LogsCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        let config = check_and_get_conf(&[], api).await?;
+        let mut query =
+            LogsQuery { since: self.since.map(|t| t.to_rfc3339()), level: self.level, after: None };
+
+        let mut all = Vec::new();
+        loop {
+            let resp = api.get_logs(&config.id, &query).await?;
+            if let Some(err) = resp.err {
+                return Err(ApiError::Rejected(err).into());
+            }
+            for entry in &resp.logs {
+                if self.follow && output::is_json() {
+                    // One document per line so consumers can parse the stream as it arrives
+                    println!("{}", serde_json::to_string(entry)?);
+                } else {
+                    print_entry(entry);
+                }
+            }
+            if !self.follow {
+                all.extend(resp.logs);
+            }
+            if resp.cursor.is_some() {
+                query.after = resp.cursor;
+            }
+            match (resp.has_more, self.follow) {
+                (true, _) => {}
+                (false, true) => tokio::time::sleep(FOLLOW_INTERVAL).await,
+                (false, false) => break,
+            }
+        }
+
+        if all.is_empty() {
+            say!("No logs found.");
+        }
+        output::emit(&json!({ "id": config.id, "logs": all }))
+    }
+}
+
+fn print_entry(entry: &LogEntry) {
+    let time = DateTime::parse_from_rfc3339(&entry.timestamp)
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| entry.timestamp.clone());
+    let level = format!("{:<5}", entry.level.to_uppercase());
+    let level = match entry.level.as_str() {
+        "error" => level.red().bold(),
+        "warn" => level.yellow().bold(),
+        "info" => level.green(),
+        _ => level.dim(),
+    };
+    let block = entry.block_number.map_or(String::new(), |b| format!(" #{}", b));
+    say!("{} {}{} {}", time.dim(), level, block.cyan(), entry.message);
+}
+
+fn parse_since(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let (num, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let num: i64 = num.parse().map_err(|_| format!("invalid duration or time: {}", s))?;
+    let duration = match unit {
+        "s" => chrono::Duration::seconds(num),
+        "m" => chrono::Duration::minutes(num),
+        "h" => chrono::Duration::hours(num),
+        "d" => chrono::Duration::days(num),
+        _ => return Err(format!("invalid duration unit in {}, expected s, m, h or d", s)),
+    };
+    Ok(Utc::now() - duration)
+}
@@ -1,17 +1,43 @@
 pub use codegen::CodegenCmd;
 pub use compile::CompileCmd;
+pub use configure::ConfigureCmd;
 pub use create::CreateCmd;
 pub use delete::DeleteCmd;
 pub use deploy::DeployCmd;
 pub use events::EventsCommand;
 pub use fork::ForkCmd;
 pub use list::ListCmd;
+pub use logs::LogsCmd;
+pub use profile::ProfileCmd;
+pub use pull::PullCmd;
+pub use push::PushCmd;
+pub use query::QueryCmd;
+pub use rollback::RollbackCmd;
+pub use status::StatusCmd;
+pub use sync_status::{check_sync, wait_for_sync, SyncStatusCmd};
+pub use topics::TopicsCmd;
+pub use versions::VersionsCmd;
+pub use watch::WatchCmd;
+pub use whoami::WhoamiCmd;
 
 mod codegen;
 mod compile;
+mod configure;
 mod create;
 mod delete;
 mod deploy;
 mod events;
 mod fork;
 mod list;
+mod logs;
+mod profile;
+mod pull;
+mod push;
+mod query;
+mod rollback;
+mod status;
+mod sync_status;
+mod topics;
+mod versions;
+mod watch;
+mod whoami;
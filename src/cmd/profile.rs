@@ -0,0 +1,76 @@
+use clap::{Parser, Subcommand};
+use serde_json::json;
+
+use crate::configure::{list_profiles, remove_profile, use_profile};
+use crate::output::{self, say};
+
+#[derive(Clone, Debug, Parser)]
+pub struct ProfileCmd {
+    #[command(subcommand)]
+    pub action: ProfileAction,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ProfileAction {
+    #[command(about = "List configured profiles")]
+    List,
+
+    #[command(about = "Make a profile the default one")]
+    Use {
+        /// Name of the profile
+        name: String,
+    },
+
+    #[command(about = "Remove a profile")]
+    Remove {
+        /// Name of the profile
+        name: String,
+    },
+}
+
+impl ProfileCmd {
+    pub fn run(self) -> eyre::Result<()> {
+        match self.action {
+            ProfileAction::List => {
+                let (active, profiles) = list_profiles()?;
+                if profiles.is_empty() {
+                    say!("No profiles configured. Use 'configure' to set up your API key.");
+                }
+                for (name, profile) in &profiles {
+                    let marker = if *name == active { "*" } else { " " };
+                    let backend =
+                        profile.secret_backend.map_or("plaintext".to_string(), |b| b.to_string());
+                    say!(
+                        "{} {:<16} {:<10} {}",
+                        marker,
+                        name,
+                        backend,
+                        profile.base_url.as_deref().unwrap_or("(default URL)")
+                    );
+                }
+                let profiles: Vec<_> = profiles
+                    .iter()
+                    .map(|(name, p)| {
+                        json!({
+                            "name": name,
+                            "secretBackend": p.secret_backend,
+                            "baseUrl": p.base_url,
+                            "webUrl": p.web_url,
+                        })
+                    })
+                    .collect();
+                output::emit(&json!({ "active": active, "profiles": profiles }))
+            }
+            ProfileAction::Use { name } => {
+                use_profile(&name)?;
+                say!("Now using profile '{}'", name);
+                output::emit(&json!({ "active": name }))
+            }
+            ProfileAction::Remove { name } => {
+                remove_profile(&name)?;
+                say!("Removed profile '{}'", name);
+                output::emit(&json!({ "removed": name }))
+            }
+        }
+    }
+}
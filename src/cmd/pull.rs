@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use serde_json::json;
+
+use crate::api_service::ApiService;
+use crate::output::{self, say};
+use crate::utils::{check_and_get_conf, check_local_edits, print_diff, write_synced_files};
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct PullCmd {
+    /// Overwrite local edits that were made since the last sync
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl PullCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        let mut config = check_and_get_conf(&[], api).await?;
+        say!("Pulling sources for id={}", config.version_id);
+
+        let version = api.get_version(&config.version_id).await?;
+        let src = Path::new("src");
+        let mut changed = Vec::new();
+        for source in &version.sources {
+            match fs::read_to_string(src.join(&source.path)) {
+                Ok(local) if local == source.code => continue,
+                Ok(local) => print_diff(&source.path, &local, &source.code, ["local", "remote"]),
+                Err(_) => say!("{} (new file)", source.path),
+            }
+            changed.push(source.path.clone());
+        }
+
+        if changed.is_empty() {
+            say!("Already up to date");
+            return output::emit(&json!({ "versionId": config.version_id, "updated": changed }));
+        }
+        check_local_edits(&config, &version.sources, self.force)?;
+
+        write_synced_files(&PathBuf::from("."), &mut config, version.sources)?;
+        say!("\nUpdated {} file(s): {}", changed.len(), changed.join(", "));
+        output::emit(&json!({ "versionId": config.version_id, "updated": changed }))
+    }
+}
@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use eyre::eyre;
+use serde_json::json;
+
+use crate::api_service::ApiService;
+use crate::errors::ApiError;
+use crate::output::{self, say};
+use crate::types::PushRequest;
+use crate::utils::{changes_json, check_and_get_conf, print_changes, read_files, source_hash};
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct PushCmd {}
+
+impl PushCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        let mut config = check_and_get_conf(&["src"], api).await?;
+        say!("Pushing sources for id={}", config.version_id);
+
+        let sources = read_files(Path::new("."))?;
+        let mut changed = Vec::new();
+        for source in &sources {
+            match config.synced.get(&source.path) {
+                Some(hash) if *hash == source_hash(&source.code) => {}
+                Some(_) => changed.push(("modified", source.path.clone())),
+                None => changed.push(("new", source.path.clone())),
+            }
+        }
+        for path in config.synced.keys() {
+            if !sources.iter().any(|s| &s.path == path) {
+                changed.push(("missing", path.clone()));
+            }
+        }
+
+        let synced = sources.iter().map(|s| (s.path.clone(), source_hash(&s.code))).collect();
+        let count = sources.len();
+        let resp = api.push_sources(&config.version_id, &PushRequest { sources }).await?;
+        if let Some(err) = resp.err {
+            return Err(ApiError::Rejected(err).into());
+        }
+        if resp.ok != Some(true) {
+            return Err(eyre!("Unexpected API response"));
+        }
+
+        if changed.is_empty() {
+            say!("No changes since the last sync");
+        } else {
+            say!("Changes since the last sync:");
+            print_changes(&changed);
+        }
+
+        config.synced = synced;
+        config.write(PathBuf::from("config.json"))?;
+        say!("Successfully pushed {} file(s)", count);
+        output::emit(&json!({
+            "versionId": config.version_id,
+            "pushed": count,
+            "changes": changes_json(&changed),
+        }))
+    }
+}
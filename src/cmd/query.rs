@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use eyre::eyre;
+use serde_json::{Map, Value};
+
+use crate::api_service::ApiService;
+use crate::output;
+use crate::types::GraphQLRequest;
+use crate::utils::check_and_get_conf;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum QueryFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct QueryCmd {
+    /// GraphQL query to run
+    #[arg(required_unless_present = "file", conflicts_with = "file")]
+    pub query: Option<String>,
+
+    /// Read the query from a file
+    #[arg(long, short)]
+    pub file: Option<PathBuf>,
+
+    /// Query variable as key=value, where value is parsed as JSON if possible
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, Value)>,
+
+    /// How to print the result. Can't be `csv` when `ghost --format json` is used
+    #[arg(long, value_enum, default_value_t)]
+    pub format: QueryFormat,
+}
+
+impl QueryCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        let config = check_and_get_conf(&[], api).await?;
+        let graph = api.get_graph(&config.version_id).await?;
+        let url = graph
+            .query_url
+            .ok_or_else(|| eyre!("The graph has no query endpoint yet. Deploy it first"))?;
+
+        let query = match (self.query, self.file) {
+            (Some(query), _) => query,
+            (None, Some(file)) => fs::read_to_string(&file)
+                .map_err(|e| eyre!("cannot read {}: {}", file.display(), e))?,
+            (None, None) => unreachable!("clap requires a query or a file"),
+        };
+        let variables = self.vars.into_iter().collect::<Map<_, _>>();
+        let resp = api.query_graph(&url, &GraphQLRequest { query, variables }).await?;
+        if let Some(errors) = resp.errors.filter(|e| !e.is_empty()) {
+            let messages: Vec<_> = errors
+                .iter()
+                .map(|e| e.get("message").and_then(Value::as_str).unwrap_or("unknown error"))
+                .collect();
+            eyre::bail!("Query failed:\n  {}", messages.join("\n  "));
+        }
+        let data = resp.data.unwrap_or(Value::Null);
+
+        if output::is_json() {
+            if self.format == QueryFormat::Csv {
+                eyre::bail!("query --format csv cannot be combined with ghost --format json");
+            }
+            return output::emit(&data);
+        }
+        match self.format {
+            QueryFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&data)?);
+                Ok(())
+            }
+            QueryFormat::Csv => print_csv(&data),
+        }
+    }
+}
+
+/// Prints the rows of a flat result set, i.e. the single list in `data` (or its `items`), as CSV.
+fn print_csv(data: &Value) -> eyre::Result<()> {
+    let rows = data
+        .as_object()
+        .and_then(|fields| fields.values().next())
+        .and_then(|v| v.as_array().or_else(|| v.get("items").and_then(Value::as_array)))
+        .ok_or_else(|| eyre!("--format csv needs a query that returns a single list"))?;
+
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        let row = row.as_object().ok_or_else(|| eyre!("--format csv needs a list of objects"))?;
+        for key in row.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    writer.write_record(&columns)?;
+    for row in rows {
+        let record = columns
+            .iter()
+            .map(|c| match row.get(*c) {
+                None | Some(Value::Null) => Ok(String::new()),
+                Some(Value::String(s)) => Ok(s.clone()),
+                Some(v @ (Value::Number(_) | Value::Bool(_))) => Ok(v.to_string()),
+                Some(_) => Err(eyre!("--format csv needs flat rows, but `{}` is nested", c)),
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn parse_var(s: &str) -> Result<(String, Value), String> {
+    let (key, value) = s.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {}", s))?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    Ok((key.to_string(), value))
+}
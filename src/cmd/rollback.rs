@@ -0,0 +1,54 @@
+use chrono::DateTime;
+use clap::Parser;
+use eyre::eyre;
+
+use crate::api_service::ApiService;
+use crate::cmd::DeployCmd;
+use crate::errors::ApiError;
+use crate::output::say;
+use crate::utils::{check_and_get_conf, confirm};
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct RollbackCmd {
+    /// Don't ask for confirmation
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+impl RollbackCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        let config = check_and_get_conf(&[], api).await?;
+        let resp = api.get_versions(&config.id).await?;
+        if let Some(err) = resp.err {
+            return Err(ApiError::Rejected(err).into());
+        }
+
+        let live = resp.versions.iter().find(|v| v.deployed);
+        let mut candidates = Vec::new();
+        for version in resp.versions.iter().filter(|v| !v.deployed) {
+            if let Some(deployed_at) = &version.deployed_at {
+                let deployed_at = DateTime::parse_from_rfc3339(deployed_at).map_err(|e| {
+                    eyre!(
+                        "Version {} has an invalid deploy time `{}`: {}",
+                        version.id,
+                        deployed_at,
+                        e
+                    )
+                })?;
+                candidates.push((deployed_at, version));
+            }
+        }
+        let (_, previous) = candidates
+            .into_iter()
+            .max_by_key(|(deployed_at, _)| *deployed_at)
+            .ok_or_else(|| eyre!("No previously deployed version to roll back to"))?;
+
+        say!("Rolling back from {} to {}", live.map_or("(none)", |v| v.id.as_str()), previous.id);
+        if !confirm(&format!("Redeploy version {}?", previous.id), self.yes)? {
+            eyre::bail!("Rollback cancelled");
+        }
+        DeployCmd { version: Some(previous.id.clone()), yes: true, ..Default::default() }
+            .run(api)
+            .await
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use clap::Parser;
+use serde_json::json;
+use yansi::Paint;
+
+use crate::api_service::ApiService;
+use crate::constants::CHAIN_NAMES;
+use crate::output::{self, say};
+use crate::utils::{changes_json, check_and_get_conf, diff_sources, print_changes, read_files};
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct StatusCmd {}
+
+impl StatusCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        let config = check_and_get_conf(&["src"], api).await?;
+        let graph = api.get_graph(&config.version_id).await?;
+        let remote = api.get_version(&config.version_id).await?;
+        let local = read_files(Path::new("."))?;
+        let changes = diff_sources(&local, &remote.sources);
+
+        let chain =
+            CHAIN_NAMES.iter().find(|(_, id)| *id == graph.chain).map_or("unknown", |c| c.0);
+        let stale = graph.latest_version_id != config.version_id;
+        let deployed = graph.deployed_version_id.as_deref() == Some(config.version_id.as_str());
+
+        say!("Graph:    {} ({})", graph.name.trim(), config.id);
+        say!("Chain:    {} ({})", chain, graph.chain);
+        if stale {
+            say!(
+                "Version:  {} {}",
+                config.version_id,
+                format!("(latest is {})", graph.latest_version_id).yellow()
+            );
+        } else {
+            say!("Version:  {} (latest)", config.version_id);
+        }
+        match &graph.deployed_version_id {
+            Some(_) if deployed => say!("Deployed: {}", "yes".green()),
+            Some(other) => say!("Deployed: no, {} is live", other),
+            None => say!("Deployed: {}", "no version is deployed".yellow()),
+        }
+        if changes.is_empty() {
+            say!("Sources:  in sync with the server");
+        } else {
+            say!("Sources:  differ from the server");
+            print_changes(&changes);
+        }
+
+        output::emit(&json!({
+            "id": config.id,
+            "name": graph.name.trim(),
+            "chain": graph.chain,
+            "versionId": config.version_id,
+            "latestVersionId": graph.latest_version_id,
+            "deployedVersionId": graph.deployed_version_id,
+            "upToDate": !stale,
+            "deployed": deployed,
+            "changes": changes_json(&changes),
+        }))
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde_json::json;
+
+use crate::api_service::ApiService;
+use crate::errors::{ApiError, GhostError};
+use crate::output::{self, say};
+use crate::types::{IndexingState, SyncStatus};
+use crate::utils::check_and_get_conf;
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct SyncStatusCmd {
+    /// Version to check (defaults to the version in config.json)
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Keep polling until the graph has caught up with the chain head
+    #[arg(long, short)]
+    pub wait: bool,
+
+    /// Seconds between polls
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval: u64,
+
+    /// Give up waiting after this many seconds
+    #[arg(long, requires = "wait")]
+    pub wait_timeout: Option<u64>,
+}
+
+impl SyncStatusCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        let version_id = match self.version {
+            Some(version_id) => version_id,
+            None => check_and_get_conf(&[], api).await?.version_id,
+        };
+
+        let status = if self.wait {
+            wait_for_sync(api, &version_id, Duration::from_secs(self.interval), self.wait_timeout)
+                .await?
+        } else {
+            let status = fetch(api, &version_id).await?;
+            say!("State:   {}", status.state);
+            if let (Some(indexed), Some(head)) = (status.indexed_block, status.chain_head) {
+                say!("Blocks:  {}/{} ({:.1}%)", indexed, head, percent(indexed, head));
+            }
+            if let Some(err) = &status.error {
+                say!("Error:   {}", err);
+            }
+            status
+        };
+
+        check_sync(&status)?;
+        output::emit(&json!({ "versionId": version_id, "sync": status }))
+    }
+}
+
+/// Turns a failed indexing status into an error so the process exits non-zero.
+pub fn check_sync(status: &SyncStatus) -> eyre::Result<()> {
+    match status.state {
+        IndexingState::Failed => Err(GhostError::Indexing(
+            status.error.clone().unwrap_or_else(|| "indexing failed".to_string()),
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// Polls the indexing status of `version_id` with a progress bar until it is synced or failed,
+/// or fails once `timeout` seconds have passed.
+pub async fn wait_for_sync(
+    api: &ApiService,
+    version_id: &str,
+    interval: Duration,
+    timeout: Option<u64>,
+) -> eyre::Result<SyncStatus> {
+    let deadline = timeout.map(|secs| (secs, Instant::now() + Duration::from_secs(secs)));
+    let bar = if output::is_json() { ProgressBar::hidden() } else { ProgressBar::new(0) };
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner} [{bar:40.cyan/blue}] {pos}/{len} blocks ({percent}%) {msg}",
+        )?
+        .progress_chars("=> "),
+    );
+    bar.enable_steady_tick(Duration::from_millis(120));
+
+    loop {
+        let status = fetch(api, version_id).await?;
+        if let (Some(indexed), Some(head)) = (status.indexed_block, status.chain_head) {
+            bar.set_length(head);
+            bar.set_position(indexed);
+        }
+        bar.set_message(status.state.to_string());
+
+        match status.state {
+            IndexingState::Synced => {
+                bar.finish_with_message("synced");
+                return Ok(status);
+            }
+            IndexingState::Failed => {
+                bar.abandon_with_message("failed");
+                return Ok(status);
+            }
+            _ => {}
+        }
+        let delay = match deadline {
+            Some((secs, deadline)) if Instant::now() >= deadline => {
+                bar.abandon_with_message("timed out");
+                return Err(GhostError::SyncTimeout(secs).into());
+            }
+            Some((_, deadline)) => interval.min(deadline - Instant::now()),
+            None => interval,
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn fetch(api: &ApiService, version_id: &str) -> eyre::Result<SyncStatus> {
+    let resp = api.get_sync_status(version_id).await?;
+    if let Some(err) = resp.err {
+        return Err(ApiError::Rejected(err).into());
+    }
+    Ok(resp.status.ok_or_else(|| ApiError::missing("SyncStatusResponse", "status"))?)
+}
+
+fn percent(indexed: u64, head: u64) -> f64 {
+    if head == 0 {
+        return 0.0;
+    }
+    indexed as f64 * 100.0 / head as f64
+}
@@ -0,0 +1,40 @@
+use std::fs;
+
+use alloy_primitives::keccak256;
+use clap::Parser;
+use serde_json::json;
+
+use crate::api_service::ApiService;
+use crate::events_file::declared_events;
+use crate::output::{self, say};
+use crate::utils::check_and_get_conf;
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct TopicsCmd {}
+
+impl TopicsCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        check_and_get_conf(&["src/events.sol"], api).await?;
+        let events = declared_events(&fs::read_to_string("src/events.sol")?)
+            .map_err(|e| eyre::eyre!("Cannot read src/events.sol: {}", e))?;
+        if events.is_empty() {
+            say!("No events declared in src/events.sol");
+        }
+
+        let mut topics = Vec::new();
+        for event in &events {
+            // anonymous events don't log their signature as a topic
+            let topic0 = (!event.anonymous).then(|| keccak256(event.signature.as_bytes()));
+            match topic0 {
+                Some(topic0) => say!("{}  {}", topic0, event.signature),
+                None => say!("{:<66}  {}", "(anonymous)", event.signature),
+            }
+            topics.push(json!({
+                "name": event.name,
+                "signature": event.signature,
+                "topic0": topic0,
+            }));
+        }
+        output::emit(&json!({ "events": topics }))
+    }
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use serde_json::json;
+
+use crate::api_service::ApiService;
+use crate::errors::ApiError;
+use crate::output::{self, say};
+use crate::types::GraphFile;
+use crate::utils::{
+    changes_json, check_and_get_conf, check_local_edits, diff_sources, fmt_time, print_diff,
+    source_hash, write_synced_files,
+};
+
+#[derive(Clone, Debug, Parser)]
+pub struct VersionsCmd {
+    #[command(subcommand)]
+    pub action: VersionsAction,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum VersionsAction {
+    #[command(about = "List every version of the graph")]
+    List,
+
+    #[command(about = "Replace the local sources with those of another version")]
+    Checkout {
+        /// Id of the version to check out
+        version_id: String,
+
+        /// Overwrite local edits that were made since the last sync
+        #[arg(long, short)]
+        force: bool,
+    },
+
+    #[command(about = "Show the source changes between two versions")]
+    Diff {
+        /// Id of the older version
+        from: String,
+
+        /// Id of the newer version (defaults to the version in config.json)
+        to: Option<String>,
+    },
+}
+
+impl VersionsCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        match self.action {
+            VersionsAction::List => list(api).await,
+            VersionsAction::Checkout { version_id, force } => {
+                checkout(api, version_id, force).await
+            }
+            VersionsAction::Diff { from, to } => diff(api, from, to).await,
+        }
+    }
+}
+
+async fn list(api: &ApiService) -> eyre::Result<()> {
+    let config = check_and_get_conf(&[], api).await?;
+    let resp = api.get_versions(&config.id).await?;
+    if let Some(err) = resp.err {
+        return Err(ApiError::Rejected(err).into());
+    }
+
+    if resp.versions.is_empty() {
+        say!("No versions found.");
+    }
+    for version in &resp.versions {
+        let marker = if version.id == config.version_id { "*" } else { " " };
+        say!(
+            "{} {}  {}  {}",
+            marker,
+            version.id,
+            fmt_time(&version.created_at).unwrap_or_else(|| version.created_at.clone()),
+            if version.deployed { "deployed" } else { "" }
+        );
+    }
+    output::emit(&json!({ "versionId": config.version_id, "versions": resp.versions }))
+}
+
+async fn checkout(api: &ApiService, version_id: String, force: bool) -> eyre::Result<()> {
+    let mut config = check_and_get_conf(&[], api).await?;
+    say!("Checking out version id={}", version_id);
+
+    let version = api.get_version(&version_id).await?;
+    check_local_edits(&config, &version.sources, force)?;
+    let src = Path::new("src");
+
+    // Drop files of the previous version that the new one doesn't have, unless they were edited
+    let mut removed = Vec::new();
+    for (path, hash) in &config.synced {
+        if version.sources.iter().any(|s| &s.path == path) {
+            continue;
+        }
+        let file = src.join(path);
+        if fs::read_to_string(&file).is_ok_and(|code| source_hash(&code) == *hash) {
+            fs::remove_file(&file)?;
+            removed.push(path.clone());
+        }
+    }
+
+    let files: Vec<_> = version.sources.iter().map(|s| s.path.clone()).collect();
+    config.version_id = version_id;
+    config.synced.clear();
+    write_synced_files(&PathBuf::from("."), &mut config, version.sources)?;
+    say!("Checked out {} file(s): {}", files.len(), files.join(", "));
+    if !removed.is_empty() {
+        say!("Removed {} file(s): {}", removed.len(), removed.join(", "));
+    }
+    output::emit(&json!({ "versionId": config.version_id, "files": files, "removed": removed }))
+}
+
+async fn diff(api: &ApiService, from: String, to: Option<String>) -> eyre::Result<()> {
+    let to = match to {
+        Some(to) => to,
+        None => check_and_get_conf(&[], api).await?.version_id,
+    };
+    let old = api.get_version(&from).await?.sources;
+    let new = api.get_version(&to).await?.sources;
+
+    let changes: Vec<_> = diff_sources(&new, &old)
+        .into_iter()
+        .map(|(status, path)| match status {
+            "new" => ("added", path),
+            "missing" => ("removed", path),
+            _ => (status, path),
+        })
+        .collect();
+    for (_, path) in &changes {
+        let code = |sources: &[GraphFile]| {
+            sources.iter().find(|s| &s.path == path).map(|s| s.code.clone()).unwrap_or_default()
+        };
+        print_diff(path, &code(&old), &code(&new), [&from, &to]);
+    }
+    if changes.is_empty() {
+        say!("No differences between {} and {}", from, to);
+    }
+
+    output::emit(&json!({ "from": from, "to": to, "changes": changes_json(&changes) }))
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use clap::Parser;
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebounceEventResult};
+use tokio::sync::mpsc;
+use yansi::Paint;
+
+use crate::api_service::ApiService;
+use crate::cmd::{CodegenCmd, CompileCmd};
+use crate::output::{self, say};
+use crate::utils::{check_and_get_conf, source_hash};
+
+const CODEGEN_FILES: [&str; 2] = ["schema.sol", "events.sol"];
+const COMPILE_FILES: [&str; 1] = ["indexer.sol"];
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct WatchCmd {
+    /// Wait this many milliseconds after the last change before running
+    #[arg(long, default_value_t = 500)]
+    pub debounce: u64,
+}
+
+impl WatchCmd {
+    pub async fn run(self, api: &ApiService) -> eyre::Result<()> {
+        check_and_get_conf(&["src/schema.sol", "src/events.sol", "src/indexer.sol"], api).await?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut debouncer = new_debouncer(
+            Duration::from_millis(self.debounce),
+            move |res: DebounceEventResult| {
+                let _ = tx.send(res);
+            },
+        )?;
+        debouncer.watcher().watch(Path::new("src"), RecursiveMode::NonRecursive)?;
+        say!("Watching src/ for changes. Press Ctrl+C to stop");
+
+        // codegen and compile rewrite files in src/, so only react to content we haven't seen yet
+        let mut seen = snapshot();
+        while let Some(res) = rx.recv().await {
+            if let Err(e) = res {
+                eprintln!("{}", format!("watch error: {e}").red());
+                continue;
+            }
+
+            let current = snapshot();
+            let changed = |files: &[&str]| files.iter().any(|f| current.get(*f) != seen.get(*f));
+            let result = if changed(&CODEGEN_FILES) {
+                say!("\nschema/events changed");
+                CodegenCmd::default().run(api).await
+            } else if changed(&COMPILE_FILES) {
+                say!("\nindexer changed");
+                CompileCmd::default().run(api).await
+            } else {
+                continue;
+            };
+
+            if let Err(e) = result {
+                output::emit_error(&e);
+            }
+            seen = snapshot();
+        }
+
+        Ok(())
+    }
+}
+
+fn snapshot() -> HashMap<&'static str, String> {
+    CODEGEN_FILES
+        .iter()
+        .chain(COMPILE_FILES.iter())
+        .filter_map(|&f| {
+            Some((f, source_hash(&fs::read_to_string(Path::new("src").join(f)).ok()?)))
+        })
+        .collect()
+}
@@ -0,0 +1,29 @@
+use clap::Parser;
+use serde_json::json;
+
+use crate::api_service::ApiService;
+use crate::configure::{config_file, Credentials};
+use crate::output::{self, say};
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct WhoamiCmd {}
+
+impl WhoamiCmd {
+    pub async fn run(self, api: &ApiService, creds: &Credentials) -> eyre::Result<()> {
+        let account = api.whoami().await?;
+        let config_file = config_file()?;
+
+        say!("Account:     {}", account);
+        say!("Profile:     {}", creds.profile);
+        say!("API URL:     {}", api.base_url());
+        say!("API key:     {}", creds.key_source);
+        say!("Config file: {}", config_file.display());
+        output::emit(&json!({
+            "account": account,
+            "profile": creds.profile,
+            "baseUrl": api.base_url(),
+            "keySource": creds.key_source,
+            "configFile": config_file,
+        }))
+    }
+}
@@ -1,21 +1,47 @@
+use std::collections::BTreeMap;
 use std::error::Error;
-use std::fmt;
-use std::fs;
 use std::path::PathBuf;
+use std::{env, fmt, fs};
 
 use dirs::home_dir;
 use eyre::{ErrReport, OptionExt};
 use serde::{Deserialize, Serialize};
+use yansi::Paint;
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::output::say;
+use crate::secrets::{write_private, SecretBackend, SecretStore};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 struct Config {
-    api_key: String,
+    /// Single key written by older versions, migrated into the default profile on load
+    #[serde(default, skip_serializing)]
+    api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Profile {
+    /// Plaintext key written by older versions, moved to a secret backend on the next `configure`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_backend: Option<SecretBackend>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_url: Option<String>,
 }
 
 #[derive(Debug)]
 pub enum ConfigError {
     ConfigNotFound,
     ApiKeyNotFound,
+    ProfileNotFound(String),
     InvalidConfig,
     IoError(std::io::Error),
     JsonError(serde_json::Error),
@@ -31,6 +57,11 @@ impl fmt::Display for ConfigError {
             ConfigError::ApiKeyNotFound => {
                 write!(f, "API key not found in config. Use 'configure' to set up your API key.")
             }
+            ConfigError::ProfileNotFound(name) => write!(
+                f,
+                "Profile '{}' not found. Use 'configure --profile {}' to set it up.",
+                name, name
+            ),
             ConfigError::InvalidConfig => write!(
                 f,
                 "Config file is empty or invalid. Use 'configure' to set up your API key."
@@ -86,35 +117,185 @@ impl Config {
             if config_str.trim().is_empty() {
                 return Err(ConfigError::InvalidConfig);
             }
-            match serde_json::from_str(&config_str) {
-                Ok(config) => Ok(config),
-                Err(_) => Err(ConfigError::InvalidConfig),
+            let mut config: Config =
+                serde_json::from_str(&config_str).map_err(|_| ConfigError::InvalidConfig)?;
+            if let Some(api_key) = config.api_key.take() {
+                config
+                    .profiles
+                    .entry(DEFAULT_PROFILE.to_string())
+                    .or_insert(Profile { api_key: Some(api_key), ..Default::default() });
             }
+            Ok(config)
         } else {
             Err(ConfigError::ConfigNotFound)
         }
     }
 
+    /// Writes the config readable only by the owner, since profiles from older versions may still
+    /// hold a plaintext key.
     fn save(&self) -> Result<(), ConfigError> {
         let config_file = Self::ensure_get_config()?;
-        let config_str = serde_json::to_string(self)?;
-        fs::write(config_file, config_str)?;
+        let config_str = serde_json::to_string_pretty(self)?;
+        write_private(&config_file, &config_str)?;
+        for (name, profile) in &self.profiles {
+            if profile.api_key.is_some() {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Warning: profile '{}' stores its API key in plaintext in {}. Run \
+                         `configure --profile {}` to move it to a secret backend",
+                        name,
+                        config_file.display(),
+                        name
+                    )
+                    .yellow()
+                );
+            }
+        }
         Ok(())
     }
+
+    fn config_dir() -> Result<PathBuf, ConfigError> {
+        let path = Self::ensure_get_config()?;
+        Ok(path.parent().map(PathBuf::from).unwrap_or_default())
+    }
+
+    fn store(backend: SecretBackend) -> Result<Box<dyn SecretStore>, ConfigError> {
+        Ok(backend.store(Self::config_dir()?))
+    }
+
+    fn active_profile(&self) -> &str {
+        self.active_profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    fn load_or_default() -> Result<Self, ConfigError> {
+        match Self::load() {
+            Err(ConfigError::ConfigNotFound) => Ok(Self::default()),
+            res => res,
+        }
+    }
+}
+
+pub fn config_file() -> Result<PathBuf, ConfigError> {
+    Config::ensure_get_config()
 }
 
-pub fn set_api_key(api_key: &str) -> Result<(), ConfigError> {
-    let config = Config { api_key: api_key.to_string() };
+/// The API key and URLs to use for a command.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub profile: String,
+    pub api_key: String,
+    /// Where the key was read from: `GHOST_API_KEY`, a secret backend or the plaintext config
+    pub key_source: String,
+    pub base_url: Option<String>,
+    pub web_url: Option<String>,
+}
+
+/// Saves `api_key` in `profile` (or the active one), keeping its URLs unless new ones are given.
+///
+/// The key goes to `backend`, or to the OS keyring if one is available and a credentials file
+/// otherwise.
+pub fn set_api_key(
+    profile: Option<&str>,
+    api_key: &str,
+    base_url: Option<String>,
+    web_url: Option<String>,
+    backend: Option<SecretBackend>,
+) -> Result<(), ConfigError> {
+    let mut config = Config::load_or_default()?;
+    let name = profile.unwrap_or(config.active_profile()).to_string();
+    let backend = backend.unwrap_or_else(SecretBackend::detect);
+    Config::store(backend)?.set(&name, api_key)?;
+
+    let entry = config.profiles.entry(name.clone()).or_default();
+    if let Some(previous) = entry.secret_backend.filter(|b| *b != backend) {
+        Config::store(previous)?.delete(&name)?;
+    }
+    entry.api_key = None;
+    entry.secret_backend = Some(backend);
+    entry.base_url = base_url.or(entry.base_url.take());
+    entry.web_url = web_url.or(entry.web_url.take());
+
+    // Move plaintext keys left by older versions out of config.json too
+    for (other, profile) in config.profiles.iter_mut() {
+        // A profile with a secret backend reads its key from there, so its plaintext copy is unused
+        if let (Some(key), None) = (profile.api_key.take(), profile.secret_backend) {
+            Config::store(backend)?.set(other, &key)?;
+            profile.secret_backend = Some(backend);
+            say!("Moved the plaintext API key of profile '{}' to {}", other, backend);
+        }
+    }
     config.save()?;
-    println!("API key saved successfully in");
+    let location = match backend {
+        SecretBackend::Keyring => "the OS keyring".to_string(),
+        SecretBackend::File => Config::config_dir()?.join("credentials.json").display().to_string(),
+    };
+    say!("API key for profile '{}' saved successfully in {}", name, location);
+    say!("Profile settings saved in {}", config_file()?.display());
     Ok(())
 }
 
-pub fn get_api_key() -> Result<String, ConfigError> {
-    let config = Config::load()?;
-    if config.api_key.trim().is_empty() {
-        Err(ConfigError::ApiKeyNotFound)
-    } else {
-        Ok(config.api_key)
+/// Resolves the credentials for `profile`, or for the active profile if none is given.
+///
+/// `GHOST_API_KEY` takes priority over any stored key and works without a global config.
+pub fn load_credentials(profile: Option<&str>) -> Result<Credentials, ConfigError> {
+    let env_key = env::var("GHOST_API_KEY").ok().filter(|k| !k.trim().is_empty());
+    let config = match Config::load() {
+        Err(ConfigError::ConfigNotFound) if env_key.is_some() => Config::default(),
+        res => res?,
+    };
+    let name = profile.unwrap_or(config.active_profile()).to_string();
+    let entry = match config.profiles.get(&name).cloned() {
+        Some(entry) => entry,
+        None if profile.is_none() && env_key.is_some() => Profile::default(),
+        None if name == DEFAULT_PROFILE && config.profiles.is_empty() => {
+            return Err(ConfigError::ApiKeyNotFound)
+        }
+        None => return Err(ConfigError::ProfileNotFound(name)),
+    };
+
+    let (api_key, key_source) = match (env_key, entry.secret_backend) {
+        (Some(key), _) => (Some(key), "GHOST_API_KEY".to_string()),
+        (None, Some(backend)) => (Config::store(backend)?.get(&name)?, backend.to_string()),
+        (None, None) => (entry.api_key.clone(), "config.json (plaintext)".to_string()),
+    };
+    match api_key {
+        Some(api_key) if !api_key.trim().is_empty() => Ok(Credentials {
+            profile: name,
+            api_key,
+            key_source,
+            base_url: entry.base_url,
+            web_url: entry.web_url,
+        }),
+        _ => Err(ConfigError::ApiKeyNotFound),
+    }
+}
+
+/// Returns the active profile name and all profiles.
+pub fn list_profiles() -> Result<(String, BTreeMap<String, Profile>), ConfigError> {
+    let config = Config::load_or_default()?;
+    Ok((config.active_profile().to_string(), config.profiles))
+}
+
+pub fn use_profile(name: &str) -> Result<(), ConfigError> {
+    let mut config = Config::load()?;
+    if !config.profiles.contains_key(name) {
+        return Err(ConfigError::ProfileNotFound(name.to_string()));
+    }
+    config.active_profile = Some(name.to_string());
+    config.save()
+}
+
+pub fn remove_profile(name: &str) -> Result<(), ConfigError> {
+    let mut config = Config::load()?;
+    let Some(profile) = config.profiles.remove(name) else {
+        return Err(ConfigError::ProfileNotFound(name.to_string()));
+    };
+    if let Some(backend) = profile.secret_backend {
+        Config::store(backend)?.delete(name)?;
+    }
+    if config.active_profile.as_deref() == Some(name) {
+        config.active_profile = None;
     }
+    config.save()
 }
@@ -0,0 +1,138 @@
+//! Error types shared across commands and the process exit codes they map to.
+use std::fmt::Debug;
+
+use serde::Serialize;
+
+use crate::configure::ConfigError;
+use crate::types::ErrorDetails;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GhostError {
+    #[error("config.json not found. This command can only be run in a ghost directory")]
+    NotGhostDir,
+    #[error("{0}")]
+    Etherscan(String),
+    #[error("Indexing failed: {0}")]
+    Indexing(String),
+    #[error("Indexing did not catch up within {0}s")]
+    SyncTimeout(u64),
+}
+
+/// Failures talking to the Ghost API, classified by HTTP status.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Unauthorized: the API key was rejected. Use 'configure' to set a valid key")]
+    Unauthorized,
+    #[error("Not found: {url}")]
+    NotFound { url: String },
+    #[error("Rate limited by the Ghost API{}", fmt_retry_after(retry_after))]
+    RateLimited { retry_after: Option<u64> },
+    #[error("Ghost API server error ({status}): {body}")]
+    Server { status: u16, body: String },
+    #[error("Unexpected API response: {reason}{}", fmt_payload(payload))]
+    MalformedResponse { reason: String, payload: String },
+    /// An `err` reply to anything but codegen, compile or deploy.
+    #[error("{0}")]
+    Rejected(ErrorDetails),
+}
+
+impl ApiError {
+    const SNIPPET_LEN: usize = 500;
+
+    /// A well-formed response that is missing required data.
+    pub fn unexpected(resp: &impl Debug) -> Self {
+        Self::MalformedResponse {
+            reason: "response is missing required fields".to_string(),
+            payload: format!("{:?}", resp),
+        }
+    }
+
+    pub fn missing(resp: &str, field: &str) -> Self {
+        Self::MalformedResponse {
+            reason: format!("{} has no `{}`", resp, field),
+            payload: String::new(),
+        }
+    }
+
+    pub fn snippet(body: &str) -> String {
+        let body = body.trim();
+        match body.char_indices().nth(Self::SNIPPET_LEN) {
+            Some((i, _)) => format!("{}...", &body[..i]),
+            None => body.to_string(),
+        }
+    }
+}
+
+fn fmt_retry_after(retry_after: &Option<u64>) -> String {
+    retry_after.map_or(String::new(), |s| format!(", retry after {}s", s))
+}
+
+fn fmt_payload(payload: &str) -> String {
+    if payload.is_empty() {
+        String::new()
+    } else {
+        format!("\nPayload: {}", ApiError::snippet(payload))
+    }
+}
+
+/// The class of a failure, which determines the process exit code.
+///
+/// | code | kind            | meaning                                             |
+/// |------|-----------------|-----------------------------------------------------|
+/// | 1    | `other`         | anything not covered below                          |
+/// | 2    | `config`        | API key missing, invalid or rejected by the API     |
+/// | 3    | `not_ghost_dir` | command must run in a directory with `config.json`  |
+/// | 4    | `build`         | codegen, compile or deploy rejected by the server   |
+/// | 5    | `network`       | the Ghost API could not be reached                  |
+/// | 6    | `api`           | error or unexpected response from the Ghost API     |
+/// | 7    | `etherscan`     | fetching an ABI from the block explorer failed      |
+/// | 8    | `indexing`      | indexing failed or didn't catch up in time          |
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Other = 1,
+    Config = 2,
+    NotGhostDir = 3,
+    Build = 4,
+    Network = 5,
+    Api = 6,
+    Etherscan = 7,
+    Indexing = 8,
+}
+
+impl FailureKind {
+    pub fn of(err: &eyre::Report) -> Self {
+        for cause in err.chain() {
+            if cause.is::<ConfigError>() {
+                return Self::Config;
+            }
+            if cause.is::<ErrorDetails>() {
+                return Self::Build;
+            }
+            if cause.is::<foundry_block_explorers::errors::EtherscanError>() {
+                return Self::Etherscan;
+            }
+            if let Some(e) = cause.downcast_ref::<GhostError>() {
+                return match e {
+                    GhostError::NotGhostDir => Self::NotGhostDir,
+                    GhostError::Etherscan(_) => Self::Etherscan,
+                    GhostError::Indexing(_) | GhostError::SyncTimeout(_) => Self::Indexing,
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<ApiError>() {
+                return match e {
+                    ApiError::Unauthorized => Self::Config,
+                    _ => Self::Api,
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return if e.is_decode() { Self::Api } else { Self::Network };
+            }
+        }
+        Self::Other
+    }
+
+    pub fn exit_code(self) -> i32 {
+        self as i32
+    }
+}
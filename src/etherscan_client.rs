@@ -1,12 +1,21 @@
+use crate::errors::GhostError;
 use crate::types::Chain;
 use crate::utils::cache_path;
 use alloy_json_abi::JsonAbi;
 use alloy_primitives::Address;
 use foundry_block_explorers::Client;
 use std::time::Duration;
+use yansi::Paint;
 
 const MAX_PROXY_REDIRECTS: usize = 3;
 
+/// The verified ABI of one contract in a proxy chain.
+pub struct ContractAbi {
+    pub address: Address,
+    pub name: String,
+    pub abi: JsonAbi,
+}
+
 pub struct EtherscanClient {
     client: Client,
 }
@@ -21,19 +30,51 @@ impl EtherscanClient {
     }
 
     pub async fn fetch_abi(&self, address: Address) -> eyre::Result<JsonAbi> {
+        let mut chain = self.resolve(address, false).await?;
+        Ok(chain.pop().map(|c| c.abi).unwrap_or_default())
+    }
+
+    /// Fetches the ABI of `address` and of every implementation it points to, proxy first.
+    ///
+    /// Proxies whose ABI can't be parsed are skipped with a warning.
+    pub async fn fetch_abi_chain(&self, address: Address) -> eyre::Result<Vec<ContractAbi>> {
+        self.resolve(address, true).await
+    }
+
+    /// Follows proxies from `address` to the final implementation, whose ABI is always returned.
+    /// The ABIs of the proxies on the way are only parsed and returned if `proxies` is set.
+    async fn resolve(&self, address: Address, proxies: bool) -> eyre::Result<Vec<ContractAbi>> {
         let mut current_address = address;
+        let mut chain = Vec::new();
 
         for _ in 0..MAX_PROXY_REDIRECTS {
             let code = self.client.contract_source_code(current_address).await?;
-            let item = code.items.first().ok_or_else(|| eyre::eyre!("No item found"))?;
+            let item = code.items.first().ok_or_else(|| {
+                GhostError::Etherscan(format!("No item found for {}", current_address))
+            })?;
+            let name = item.contract_name.clone();
 
-            if let Some(implementation_address) = item.implementation {
-                current_address = implementation_address;
-            } else {
-                return Ok(item.abi()?);
+            let Some(implementation_address) = item.implementation else {
+                chain.push(ContractAbi { address: current_address, name, abi: item.abi()? });
+                return Ok(chain);
+            };
+            if proxies {
+                match item.abi() {
+                    Ok(abi) => chain.push(ContractAbi { address: current_address, name, abi }),
+                    Err(e) => eprintln!(
+                        "{}",
+                        format!(
+                            "Warning: skipping the ABI of proxy {} ({}): {}",
+                            name, current_address, e
+                        )
+                        .yellow()
+                    ),
+                }
             }
+            current_address = implementation_address;
         }
 
-        Err(eyre::eyre!("ABI not found after 3 redirects"))
+        Err(GhostError::Etherscan(format!("ABI not found after {} redirects", MAX_PROXY_REDIRECTS))
+            .into())
     }
 }
@@ -0,0 +1,510 @@
+//! Reads the declarations in `events.sol` and merges generated struct and event definitions into it.
+//!
+//! Declarations already in the file are matched by name. Ones with the same canonical types
+//! (`uint` and `uint256`, `Market.Order` and an identical `Market_Order`) are skipped, and ones
+//! whose types differ are reported as conflicts and left untouched, so the merge never rewrites
+//! anything the user wrote.
+use crate::abi_processor::{SolEvent, SolStruct};
+
+/// Header of the block new events are added to when the file doesn't have one yet.
+const EVENTS_BLOCK: &str = "interface Events {";
+
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    pub content: String,
+    pub added: Vec<String>,
+    pub skipped: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Debug)]
+pub struct Conflict {
+    pub name: String,
+    pub existing: String,
+    pub generated: String,
+}
+
+/// An event declared in `events.sol`.
+#[derive(Debug)]
+pub struct DeclaredEvent {
+    pub name: String,
+    /// Canonical signature with structs expanded to tuples, e.g. `Transfer(address,address,uint256)`
+    pub signature: String,
+    pub anonymous: bool,
+}
+
+/// A struct or event declaration reduced to what makes it compatible: its name and types.
+#[derive(Clone, Debug)]
+struct Decl {
+    name: String,
+    types: Vec<String>,
+    anonymous: bool,
+}
+
+impl Decl {
+    fn signature(&self) -> String {
+        let anonymous = if self.anonymous { " anonymous" } else { "" };
+        format!("{}({}){}", self.name, self.types.join(","), anonymous)
+    }
+}
+
+/// Merges `structs` and `events` into the `events.sol` source `existing`.
+///
+/// Fails instead of returning content whose declarations or delimiters no longer line up.
+pub fn merge(
+    existing: &str,
+    structs: &[SolStruct],
+    events: &[SolEvent],
+) -> Result<MergeReport, String> {
+    let code = mask_comments(existing);
+    let existing_structs = parse_decls(&code, "struct", ('{', '}'), ';');
+    let existing_events = parse_decls(&code, "event", ('(', ')'), ',');
+    let enums: Vec<_> =
+        parse_decls(&code, "enum", ('{', '}'), ',').into_iter().map(|e| e.name).collect();
+
+    let generated_structs: Vec<Decl> = structs
+        .iter()
+        .map(|s| Decl {
+            name: s.name.clone(),
+            types: s.fields.iter().map(|f| normalize(&f.ty)).collect(),
+            anonymous: false,
+        })
+        .collect();
+    // Struct types resolve to the file's declarations first, then to the generated ones
+    let known_structs: Vec<Decl> = existing_structs
+        .iter()
+        .chain(
+            generated_structs.iter().filter(|g| existing_structs.iter().all(|e| e.name != g.name)),
+        )
+        .cloned()
+        .collect();
+    let same = |a: &Decl, b: &Decl| {
+        a.anonymous == b.anonymous
+            && a.types.len() == b.types.len()
+            && a.types.iter().zip(&b.types).all(|(x, y)| {
+                let canonical = |ty: &str| {
+                    canonical_type(ty, &known_structs, &enums, 0).unwrap_or_else(|_| normalize(ty))
+                };
+                canonical(x) == canonical(y)
+            })
+    };
+
+    let mut report = MergeReport::default();
+    let mut new_structs = Vec::new();
+    for (s, decl) in structs.iter().zip(generated_structs.clone()) {
+        if check(&mut report, "struct", decl, &existing_structs, same) {
+            new_structs.push(s.to_string());
+        }
+    }
+    let mut new_events = Vec::new();
+    for e in events {
+        let decl = Decl {
+            name: e.name.clone(),
+            types: e.params.iter().map(|p| normalize(&p.ty)).collect(),
+            anonymous: e.anonymous,
+        };
+        if check(&mut report, "event", decl, &existing_events, same) {
+            new_events.push(e.to_string());
+        }
+    }
+
+    report.content = insert(existing, &code, &new_structs, &new_events);
+    let merged = mask_comments(&report.content);
+    check_balanced(&merged)?;
+    let declared = parse_decls(&merged, "event", ('(', ')'), ',').len();
+    if declared != existing_events.len() + new_events.len() {
+        return Err("merged events.sol has unexpected declarations".to_string());
+    }
+    Ok(report)
+}
+
+/// Lists the events declared in `code` with their canonical signatures.
+pub fn declared_events(code: &str) -> Result<Vec<DeclaredEvent>, String> {
+    let code = mask_comments(code);
+    let structs = parse_decls(&code, "struct", ('{', '}'), ';');
+    let enums: Vec<_> =
+        parse_decls(&code, "enum", ('{', '}'), ',').into_iter().map(|e| e.name).collect();
+
+    parse_decls(&code, "event", ('(', ')'), ',')
+        .into_iter()
+        .map(|event| {
+            let types = event
+                .types
+                .iter()
+                .map(|ty| canonical_type(ty, &structs, &enums, 0))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("{} in event {}", e, event.name))?;
+            Ok(DeclaredEvent {
+                signature: format!("{}({})", event.name, types.join(",")),
+                name: event.name,
+                anonymous: event.anonymous,
+            })
+        })
+        .collect()
+}
+
+/// The ABI type for a Solidity type, e.g. `uint` is `uint256` and a struct becomes its tuple.
+fn canonical_type(
+    ty: &str,
+    structs: &[Decl],
+    enums: &[String],
+    depth: usize,
+) -> Result<String, String> {
+    let (base, suffix) = ty.split_at(ty.find('[').unwrap_or(ty.len()));
+    let base = base.trim().trim_end_matches(" payable");
+    // `Contract.Struct` refers to the same struct as `Struct`
+    let name = base.rsplit('.').next().unwrap_or(base);
+
+    let canonical = match name {
+        "uint" => "uint256".to_string(),
+        "int" => "int256".to_string(),
+        "byte" => "bytes1".to_string(),
+        _ if is_elementary(name) => name.to_string(),
+        _ if enums.iter().any(|e| e == name) => "uint8".to_string(),
+        _ => {
+            let decl = structs
+                .iter()
+                .find(|s| s.name == name)
+                .filter(|_| depth < 32)
+                .ok_or_else(|| format!("unknown type `{}`", base))?;
+            let fields = decl
+                .types
+                .iter()
+                .map(|t| canonical_type(t, structs, enums, depth + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("({})", fields.join(","))
+        }
+    };
+    Ok(format!("{}{}", canonical, suffix.replace(' ', "")))
+}
+
+fn is_elementary(ty: &str) -> bool {
+    let sized = |prefix: &str| {
+        ty.strip_prefix(prefix).is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+    };
+    matches!(ty, "address" | "bool" | "string" | "bytes" | "function")
+        || sized("uint")
+        || sized("int")
+        || sized("bytes")
+}
+
+/// Records the outcome for `decl` and returns whether it should be added.
+fn check(
+    report: &mut MergeReport,
+    kind: &str,
+    decl: Decl,
+    existing: &[Decl],
+    same: impl Fn(&Decl, &Decl) -> bool,
+) -> bool {
+    let label = format!("{} {}", kind, decl.signature());
+    match existing.iter().find(|d| d.name == decl.name) {
+        None => {
+            report.added.push(label);
+            true
+        }
+        Some(found) if same(found, &decl) => {
+            report.skipped.push(label);
+            false
+        }
+        Some(found) => {
+            report.conflicts.push(Conflict {
+                name: decl.name.clone(),
+                existing: format!("{} {}", kind, found.signature()),
+                generated: label,
+            });
+            false
+        }
+    }
+}
+
+/// Inserts the declarations into `existing`. `code` is `existing` with comments masked, so the
+/// offsets found in it also apply to `existing`.
+fn insert(existing: &str, code: &str, structs: &[String], events: &[String]) -> String {
+    if structs.is_empty() && events.is_empty() {
+        return existing.to_string();
+    }
+    let structs: String = structs.iter().map(|s| format!("{}\n\n", s)).collect();
+    let events: String = events.iter().map(|e| format!("    {}\n", e)).collect();
+
+    let Some((start, end)) = find_events_block(code) else {
+        let mut content = existing.trim_end().to_string();
+        content.push_str(&format!("\n\n{}{}\n{}}}\n", structs, EVENTS_BLOCK, events));
+        return content;
+    };
+    // Append after the last declaration, keeping any comments between it and the closing brace
+    let body_end = code[..end].trim_end().len();
+    let separator = if existing[body_end..end].contains('\n') { "" } else { "\n" };
+    format!(
+        "{}{}{}\n{}{}{}",
+        &existing[..start],
+        structs,
+        &existing[start..body_end],
+        events.trim_end_matches('\n'),
+        separator,
+        &existing[body_end..]
+    )
+}
+
+/// Byte offsets of the start of the events block's header line and of its closing brace.
+fn find_events_block(code: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    let mut start = None;
+    for line in code.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let is_header = trimmed.ends_with('{')
+            && (trimmed.starts_with("interface ") || trimmed.starts_with("events"));
+        if is_header {
+            start = Some(offset);
+        }
+        offset += line.len();
+    }
+
+    let start = start?;
+    let mut depth = 0;
+    for (i, c) in code[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, start + i));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Finds every `keyword Name <open> items <close>` and returns the types of its items.
+fn parse_decls(code: &str, keyword: &str, (open, close): (char, char), sep: char) -> Vec<Decl> {
+    let mut decls = Vec::new();
+    let mut rest = code;
+    while let Some(pos) = rest.find(keyword) {
+        let before_ok = rest[..pos].chars().next_back().is_none_or(|c| !is_ident(c));
+        let after = &rest[pos + keyword.len()..];
+        rest = after;
+        if !before_ok || !after.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let (Some(o), Some(c)) = (after.find(open), after.find(close)) else { break };
+        if c < o {
+            continue;
+        }
+        let name = after[..o].trim();
+        if name.is_empty() || !name.chars().all(is_ident) {
+            continue;
+        }
+        let types = after[o + 1..c]
+            .split(sep)
+            .filter_map(|item| item_type(item, keyword == "event"))
+            .collect();
+        let anonymous = after[c + 1..].trim_start().starts_with("anonymous");
+        decls.push(Decl { name: name.to_string(), types, anonymous });
+        rest = &after[c..];
+    }
+    decls
+}
+
+/// The type of a struct field or event parameter, without its name or `indexed`.
+fn item_type(item: &str, is_event: bool) -> Option<String> {
+    let tokens: Vec<&str> = item.split_whitespace().filter(|t| *t != "indexed").collect();
+    match tokens.len() {
+        0 => None,
+        // unnamed event parameter
+        1 if is_event => Some(normalize(tokens[0])),
+        n => Some(normalize(&tokens[..n - 1].join(" "))),
+    }
+}
+
+fn normalize(ty: &str) -> String {
+    ty.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Replaces every comment with spaces, keeping line breaks, so byte offsets stay the same.
+fn mask_comments(code: &str) -> String {
+    let bytes = code.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match &bytes[i..] {
+            [b'/', b'/', ..] => {
+                bytes[i..].iter().position(|b| *b == b'\n').map_or(bytes.len(), |n| i + n)
+            }
+            [b'/', b'*', ..] => bytes[i + 2..]
+                .windows(2)
+                .position(|w| w == b"*/")
+                .map_or(bytes.len(), |n| i + 2 + n + 2),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        for b in &mut out[i..end] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+        i = end;
+    }
+    // Comment bytes are all replaced with ASCII spaces, so no character is split
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// Checks that braces and parentheses outside comments are balanced.
+fn check_balanced(code: &str) -> Result<(), String> {
+    let mut stack = Vec::new();
+    for c in code.chars() {
+        match c {
+            '{' | '(' => stack.push(c),
+            '}' | ')' => {
+                let open = if c == '}' { '{' } else { '(' };
+                if stack.pop() != Some(open) {
+                    return Err(format!("unbalanced `{}` in events.sol", c));
+                }
+            }
+            _ => {}
+        }
+    }
+    match stack.last() {
+        Some(c) => Err(format!("unclosed `{}` in events.sol", c)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi_processor::{SolEventParam, SolField};
+
+    fn sol_struct(name: &str, fields: &[(&str, &str)]) -> SolStruct {
+        SolStruct {
+            name: name.to_string(),
+            fields: fields
+                .iter()
+                .map(|(ty, name)| SolField { name: name.to_string(), ty: ty.to_string() })
+                .collect(),
+        }
+    }
+
+    fn sol_event(name: &str, params: &[&str], anonymous: bool) -> SolEvent {
+        SolEvent {
+            name: name.to_string(),
+            params: params
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| SolEventParam {
+                    name: format!("arg{}", i),
+                    ty: ty.to_string(),
+                    indexed: false,
+                })
+                .collect(),
+            anonymous,
+        }
+    }
+
+    #[test]
+    fn masks_comments_in_place() {
+        let code = "a // x }\nb /* { é */ c";
+        let masked = mask_comments(code);
+        assert_eq!(masked.len(), code.len());
+        assert!(!masked.contains('}') && !masked.contains('{'));
+        assert!(masked.starts_with("a ") && masked.ends_with(" c"));
+    }
+
+    #[test]
+    fn appends_to_events_block_ignoring_braces_in_comments() {
+        let existing = "interface Events {\n    // closes a position }\n    event A(uint256 a);\n    /* trailing { note */\n}\n";
+        let report = merge(existing, &[], &[sol_event("B", &["address"], false)]).unwrap();
+        assert_eq!(
+            report.content,
+            "interface Events {\n    // closes a position }\n    event A(uint256 a);\n    event B(address arg0);\n    /* trailing { note */\n}\n"
+        );
+        assert_eq!(report.added, ["event B(address)"]);
+    }
+
+    #[test]
+    fn inserts_structs_before_block_and_creates_missing_block() {
+        let order = sol_struct("Market_Order", &[("address", "maker")]);
+        let filled = sol_event("OrderFilled", &["Market_Order"], false);
+
+        let report = merge(
+            "events {\n    event A();\n}\n",
+            std::slice::from_ref(&order),
+            std::slice::from_ref(&filled),
+        )
+        .unwrap();
+        assert_eq!(
+            report.content,
+            "struct Market_Order {\n    address maker;\n}\n\nevents {\n    event A();\n    event OrderFilled(Market_Order arg0);\n}\n"
+        );
+
+        let report = merge("// no events yet\n", &[order], &[filled]).unwrap();
+        assert_eq!(
+            report.content,
+            "// no events yet\n\nstruct Market_Order {\n    address maker;\n}\n\ninterface Events {\n    event OrderFilled(Market_Order arg0);\n}\n"
+        );
+    }
+
+    #[test]
+    fn skips_declarations_with_same_canonical_types() {
+        let existing = "struct Order { address maker; }\ninterface Events {\n    event Transfer(address indexed a, address payable b, uint value);\n    event Filled(Market.Order);\n}\n";
+        let events = [
+            sol_event("Transfer", &["address", "address", "uint256"], false),
+            sol_event("Filled", &["Market_Order"], false),
+        ];
+        let structs = [sol_struct("Market_Order", &[("address", "maker")])];
+        let report = merge(existing, &structs, &events).unwrap();
+        assert_eq!(
+            report.skipped,
+            ["event Transfer(address,address,uint256)", "event Filled(Market_Order)"]
+        );
+        assert_eq!(report.added, ["struct Market_Order(address)"]);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn reports_conflicts_including_anonymous() {
+        let existing =
+            "interface Events {\n    event Anon(address, uint256);\n    event T(uint8 a);\n}\n";
+        let events =
+            [sol_event("Anon", &["address", "uint256"], true), sol_event("T", &["uint256"], false)];
+        let report = merge(existing, &[], &events).unwrap();
+        assert_eq!(report.content, existing);
+        let conflicts: Vec<_> =
+            report.conflicts.iter().map(|c| (c.existing.as_str(), c.generated.as_str())).collect();
+        assert_eq!(
+            conflicts,
+            [
+                ("event Anon(address,uint256)", "event Anon(address,uint256) anonymous"),
+                ("event T(uint8)", "event T(uint256)")
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_unbalanced_files() {
+        let existing = "interface Events {\n    event A(;\n}\n";
+        assert!(merge(existing, &[], &[sol_event("B", &[], false)]).is_err());
+    }
+
+    #[test]
+    fn lists_canonical_signatures() {
+        let code = "enum Kind { A, B }\nstruct Range { int24 lo; int24 hi; }\nstruct Pool { uint id; Kind kind; Range range; }\n// event Commented(uint);\ninterface Events {\n    event PoolsCreated(Pool[] indexed pools, Pool[3] fixed);\n    event Raw(uint, bytes32 indexed) anonymous;\n}\n";
+        let events = declared_events(code).unwrap();
+        let signatures: Vec<_> =
+            events.iter().map(|e| (e.signature.as_str(), e.anonymous)).collect();
+        assert_eq!(
+            signatures,
+            [
+                ("PoolsCreated((uint256,uint8,(int24,int24))[],(uint256,uint8,(int24,int24))[3])", false),
+                ("Raw(uint256,bytes32)", true)
+            ]
+        );
+        assert!(declared_events("interface E { event X(Missing m); }").is_err());
+    }
+}
@@ -1,13 +1,18 @@
-use std::env;
+use std::time::Duration;
 
 use clap::{Parser, Subcommand};
 use dotenv::dotenv;
+use eyre::WrapErr;
 
-use api_service::ApiService;
+use api_service::{ApiService, HttpOptions};
 
 use crate::cmd::{
-    CodegenCmd, CompileCmd, CreateCmd, DeleteCmd, DeployCmd, EventsCommand, ForkCmd, ListCmd,
+    CodegenCmd, CompileCmd, ConfigureCmd, CreateCmd, DeleteCmd, DeployCmd, EventsCommand, ForkCmd,
+    ListCmd, LogsCmd, ProfileCmd, PullCmd, PushCmd, QueryCmd, RollbackCmd, StatusCmd,
+    SyncStatusCmd, TopicsCmd, VersionsCmd, WatchCmd, WhoamiCmd,
 };
+use crate::errors::FailureKind;
+use crate::output::OutputFormat;
 use crate::utils::install_handler;
 
 mod abi_processor;
@@ -15,7 +20,11 @@ mod api_service;
 mod cmd;
 mod configure;
 mod constants;
+mod errors;
 mod etherscan_client;
+mod events_file;
+mod output;
+mod secrets;
 mod types;
 mod utils;
 
@@ -27,6 +36,27 @@ mod utils;
     long_about = None
 )]
 struct Cli {
+    /// Output format. `json` prints a single JSON document per command, including errors.
+    /// Must come before the command, since `query` and `events` have their own `--format`
+    #[arg(long, value_enum, default_value_t, env = "GHOST_FORMAT")]
+    format: OutputFormat,
+
+    /// Profile from the global config to use (defaults to the active profile)
+    #[arg(long, global = true, env = "GHOST_PROFILE")]
+    profile: Option<String>,
+
+    /// Timeout for each request to the Ghost API, in seconds
+    #[arg(long, global = true, env = "GHOST_TIMEOUT", default_value_t = 120)]
+    timeout: u64,
+
+    /// Timeout for connecting to the Ghost API, in seconds
+    #[arg(long, global = true, env = "GHOST_CONNECT_TIMEOUT", default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// How many times to retry requests that failed with a transient error
+    #[arg(long, global = true, env = "GHOST_RETRIES", default_value_t = 3)]
+    retries: u32,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -34,10 +64,13 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Configure the Ghost API key")]
-    Configure {
-        #[arg(help = "Set the Ghost API key")]
-        api_key: String,
-    },
+    Configure(ConfigureCmd),
+
+    #[command(about = "Show the account, profile and config in use")]
+    Whoami(WhoamiCmd),
+
+    #[command(about = "Manage configured profiles")]
+    Profile(ProfileCmd),
 
     #[command(about = "Create a new graph")]
     Create(CreateCmd),
@@ -51,6 +84,18 @@ enum Commands {
     #[command(about = "Deploy the graph")]
     Deploy(DeployCmd),
 
+    #[command(about = "Redeploy the previously live version of the graph")]
+    Rollback(RollbackCmd),
+
+    #[command(about = "Show how far the deployed graph has indexed")]
+    SyncStatus(SyncStatusCmd),
+
+    #[command(about = "Show runtime logs of the deployed graph")]
+    Logs(LogsCmd),
+
+    #[command(about = "Run a GraphQL query against the deployed graph")]
+    Query(QueryCmd),
+
     #[command(about = "List all my graphs")]
     List(ListCmd),
 
@@ -62,10 +107,28 @@ enum Commands {
 
     #[command(about = "Fetch events from contract ABI")]
     Events(EventsCommand),
+
+    #[command(about = "Print the signature and topic0 of every event in src/events.sol")]
+    Topics(TopicsCmd),
+
+    #[command(about = "Sync local sources with the server version")]
+    Pull(PullCmd),
+
+    #[command(about = "Upload local sources to the server version without compiling")]
+    Push(PushCmd),
+
+    #[command(about = "Show the graph's version, deploy state and local changes")]
+    Status(StatusCmd),
+
+    #[command(about = "List, check out and diff versions of the graph")]
+    Versions(VersionsCmd),
+
+    #[command(about = "Re-run codegen/compile when source files change")]
+    Watch(WatchCmd),
 }
 
 #[tokio::main]
-async fn main() -> eyre::Result<()> {
+async fn main() {
     dotenv().ok();
     let enable = yansi::Condition::os_support();
     yansi::whenever(yansi::Condition::cached(enable));
@@ -74,28 +137,36 @@ async fn main() -> eyre::Result<()> {
     }
 
     let cli = Cli::parse();
+    output::set_format(cli.format);
 
-    if let Some(Commands::Configure { api_key }) = &cli.command {
-        configure::set_api_key(api_key)?;
-        return Ok(());
+    if let Err(e) = run(cli).await {
+        output::emit_error(&e);
+        std::process::exit(FailureKind::of(&e).exit_code());
     }
+}
 
-    let api_key = match configure::get_api_key() {
-        Ok(key) => key,
-        Err(e) => {
-            eprintln!(
-                "Failed to retrieve API key: {}. Please run the 'configure' command first.",
-                e
-            );
-            return Ok(());
-        }
+async fn run(cli: Cli) -> eyre::Result<()> {
+    let http = HttpOptions {
+        timeout: Duration::from_secs(cli.timeout),
+        connect_timeout: Duration::from_secs(cli.connect_timeout),
+        retries: cli.retries,
     };
 
-    let base_url =
-        env::var("GHOST_BASE_URL").unwrap_or_else(|_| "https://api.ghostlogs.xyz".to_string());
-    let web_base_url =
-        env::var("GHOST_WEB_BASE_URL").unwrap_or_else(|_| "https://app.ghostlogs.xyz".to_string());
-    let api_service = ApiService::new(base_url, api_key, web_base_url);
+    match cli.command {
+        Some(Commands::Configure(cmd)) => return cmd.run(cli.profile.as_deref(), http).await,
+        Some(Commands::Profile(cmd)) => return cmd.run(),
+        _ => {}
+    }
+
+    let creds = configure::load_credentials(cli.profile.as_deref())
+        .wrap_err("Failed to retrieve API key. Please run the 'configure' command first.")?;
+    let api_service = ApiService::with_defaults(
+        creds.api_key.clone(),
+        creds.base_url.clone(),
+        creds.web_url.clone(),
+        cli.profile.is_some(),
+    )
+    .with_options(http)?;
 
     match cli.command {
         Some(Commands::Create(cmd)) => {
@@ -110,6 +181,18 @@ async fn main() -> eyre::Result<()> {
         Some(Commands::Deploy(cmd)) => {
             cmd.run(&api_service).await?;
         }
+        Some(Commands::Rollback(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::SyncStatus(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Logs(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Query(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
         Some(Commands::List(cmd)) => {
             cmd.run(&api_service).await?;
         }
@@ -122,6 +205,27 @@ async fn main() -> eyre::Result<()> {
         Some(Commands::Events(cmd)) => {
             cmd.run(&api_service).await?;
         }
+        Some(Commands::Topics(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Pull(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Push(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Status(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Versions(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Watch(cmd)) => {
+            cmd.run(&api_service).await?;
+        }
+        Some(Commands::Whoami(cmd)) => {
+            cmd.run(&api_service, &creds).await?;
+        }
         _ => {}
     }
 
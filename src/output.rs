@@ -0,0 +1,72 @@
+//! Switches command output between human-readable text and machine-readable JSON.
+//!
+//! In JSON mode every command prints exactly one JSON document to stdout: its result on success
+//! (see [`emit`]) or an error document on failure (see [`emit_error`]). All prose goes through
+//! [`say!`] and is suppressed so stdout stays parseable.
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::errors::FailureKind;
+use crate::types::ErrorDetails;
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get().copied().unwrap_or_default() == OutputFormat::Json
+}
+
+/// Like `println!`, but only prints in text mode.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_json() {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use say;
+
+/// Prints `value` as the command's JSON result. Does nothing in text mode.
+pub fn emit<T: Serialize>(value: &T) -> eyre::Result<()> {
+    if is_json() {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    }
+    Ok(())
+}
+
+/// Reports `err` to the user.
+///
+/// In JSON mode this prints `{"error": {"kind": ..., "exitCode": ..., "message": ..., "details":
+/// ...}}` to stdout, where `kind`/`exitCode` come from [`FailureKind`] and `details` holds the
+/// server's [`ErrorDetails`] for codegen/compile/deploy failures and is `null` otherwise.
+pub fn emit_error(err: &eyre::Report) {
+    if is_json() {
+        let kind = FailureKind::of(err);
+        let details = err.chain().find_map(|e| e.downcast_ref::<ErrorDetails>());
+        let doc = json!({
+            "error": {
+                "kind": kind,
+                "exitCode": kind.exit_code(),
+                "message": format!("{:#}", err),
+                "details": details,
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&doc).unwrap_or_default());
+    } else {
+        eprintln!("Error: {:?}", err);
+    }
+}
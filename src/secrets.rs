@@ -0,0 +1,165 @@
+//! Storage backends for API keys, so they don't have to live in the plaintext global config.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use yansi::Paint;
+
+use crate::configure::ConfigError;
+
+const KEYRING_SERVICE: &str = "ghost-cli";
+
+pub trait SecretStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, ConfigError>;
+    fn set(&self, profile: &str, secret: &str) -> Result<(), ConfigError>;
+    fn delete(&self, profile: &str) -> Result<(), ConfigError>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretBackend {
+    /// OS keyring (Secret Service, macOS Keychain, Windows Credential Manager)
+    Keyring,
+    /// `credentials.json` next to the global config, readable only by the owner
+    File,
+}
+
+impl SecretBackend {
+    /// The keyring if one is reachable on this machine, the credentials file otherwise.
+    pub fn detect() -> Self {
+        match keyring::Entry::new(KEYRING_SERVICE, "probe").and_then(|e| e.get_password()) {
+            Ok(_) | Err(keyring::Error::NoEntry) => Self::Keyring,
+            Err(_) => Self::File,
+        }
+    }
+
+    pub fn store(self, config_dir: PathBuf) -> Box<dyn SecretStore> {
+        match self {
+            Self::Keyring => Box::new(KeyringStore),
+            Self::File => Box::new(FileStore { path: config_dir.join("credentials.json") }),
+        }
+    }
+}
+
+impl fmt::Display for SecretBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keyring => write!(f, "keyring"),
+            Self::File => write!(f, "file"),
+        }
+    }
+}
+
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(profile: &str) -> Result<keyring::Entry, ConfigError> {
+        keyring::Entry::new(KEYRING_SERVICE, profile).map_err(keyring_error)
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, ConfigError> {
+        match Self::entry(profile)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+
+    fn set(&self, profile: &str, secret: &str) -> Result<(), ConfigError> {
+        Self::entry(profile)?.set_password(secret).map_err(keyring_error)
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), ConfigError> {
+        match Self::entry(profile)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+}
+
+fn keyring_error(e: keyring::Error) -> ConfigError {
+    ConfigError::Custom(format!("keyring: {}", e))
+}
+
+/// Writes `contents` to `path`, readable and writable only by the owner.
+pub fn write_private(path: &Path, contents: &str) -> Result<(), ConfigError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // `mode` only applies on creation, so also tighten files made by older versions
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file = options.open(path)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    fn read(&self) -> Result<BTreeMap<String, String>, ConfigError> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        self.warn_if_loose();
+        let contents = fs::read_to_string(&self.path)?;
+        serde_json::from_str(&contents).map_err(|_| ConfigError::InvalidConfig)
+    }
+
+    fn write(&self, secrets: &BTreeMap<String, String>) -> Result<(), ConfigError> {
+        write_private(&self.path, &serde_json::to_string_pretty(secrets)?)
+    }
+
+    fn warn_if_loose(&self) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Ok(meta) = fs::metadata(&self.path) {
+                if meta.permissions().mode() & 0o077 != 0 {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "Warning: {} is accessible by other users. Run `chmod 600 {}`",
+                            self.path.display(),
+                            self.path.display()
+                        )
+                        .yellow()
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl SecretStore for FileStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, ConfigError> {
+        Ok(self.read()?.remove(profile))
+    }
+
+    fn set(&self, profile: &str, secret: &str) -> Result<(), ConfigError> {
+        let mut secrets = self.read()?;
+        secrets.insert(profile.to_string(), secret.to_string());
+        self.write(&secrets)
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), ConfigError> {
+        let mut secrets = self.read()?;
+        if secrets.remove(profile).is_some() {
+            self.write(&secrets)?;
+        }
+        Ok(())
+    }
+}
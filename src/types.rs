@@ -1,14 +1,16 @@
-use std::{fmt, fs};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::{collections::BTreeMap, fmt, fs};
 
 use alloy_chains::Chain as AlloyChain;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
 use crate::constants::*;
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum Chain {
+    #[default]
     EthMainnet,
     EthSepolia,
     BaseMainnet,
@@ -63,12 +65,6 @@ impl Chain {
     }
 }
 
-impl Default for Chain {
-    fn default() -> Self {
-        Self::EthMainnet
-    }
-}
-
 impl TryFrom<u64> for Chain {
     type Error = String;
 
@@ -127,6 +123,9 @@ pub struct GraphConfig {
     pub id: String,
     pub version_id: String,
     pub chain: Option<Chain>,
+    /// Hashes of the sources as last synced with the server, keyed by path relative to `src/`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub synced: BTreeMap<String, String>,
 }
 
 impl GraphConfig {
@@ -149,7 +148,7 @@ pub struct CreateRequest {
     pub chain: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GraphFile {
     pub path: String,
@@ -192,6 +191,11 @@ pub struct CompileRequest {
     pub indexer_code: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct PushRequest {
+    pub sources: Vec<GraphFile>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileError {
     pub file: String,
@@ -243,11 +247,19 @@ impl fmt::Display for ErrorDetails {
     }
 }
 
+impl std::error::Error for ErrorDetails {}
+
 #[derive(Deserialize)]
 pub struct GhostVersion {
     pub sources: Vec<GraphFile>,
 }
 
+#[derive(Deserialize)]
+pub struct VersionResponse {
+    pub err: Option<ErrorDetails>,
+    pub version: Option<GhostVersion>,
+}
+
 #[derive(Deserialize)]
 pub struct CodegenResponse {
     pub err: Option<ErrorDetails>,
@@ -260,13 +272,96 @@ pub struct CompileResponse {
     pub version: Option<GhostVersion>,
 }
 
+#[derive(Deserialize)]
+pub struct PushResponse {
+    pub err: Option<ErrorDetails>,
+    pub ok: Option<bool>,
+}
+
 #[derive(Deserialize)]
 pub struct DeployResponse {
     pub err: Option<ErrorDetails>,
     pub ok: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexingState {
+    Pending,
+    Syncing,
+    Synced,
+    Failed,
+}
+
+impl fmt::Display for IndexingState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Syncing => write!(f, "syncing"),
+            Self::Synced => write!(f, "synced"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub state: IndexingState,
+    pub indexed_block: Option<u64>,
+    pub chain_head: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SyncStatusResponse {
+    pub err: Option<ErrorDetails>,
+    pub status: Option<SyncStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub message: String,
+    pub block_number: Option<u64>,
+    pub tx_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogsResponse {
+    pub err: Option<ErrorDetails>,
+    #[serde(default)]
+    pub logs: Vec<LogEntry>,
+    /// Position after the last returned entry, to pass as `after` in the next request
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LogsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// Minimum level to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<LogLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Graph {
     pub latest_version_id: String,
@@ -274,6 +369,66 @@ pub struct Graph {
     pub description: Option<String>,
     pub chain: u64,
     pub created_at: String,
+    #[serde(default)]
+    pub deployed_version_id: Option<String>,
+    /// GraphQL endpoint of the deployed graph
+    #[serde(default)]
+    pub query_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphQLRequest {
+    pub query: String,
+    pub variables: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct GraphQLResponse {
+    pub data: Option<serde_json::Value>,
+    pub errors: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub id: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, &self.email) {
+            (Some(name), Some(email)) => write!(f, "{} <{}> ({})", name, email, self.id),
+            (Some(name), None) => write!(f, "{} ({})", name, self.id),
+            (None, Some(email)) => write!(f, "{} ({})", email, self.id),
+            (None, None) => write!(f, "{}", self.id),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct WhoamiResponse {
+    pub err: Option<ErrorDetails>,
+    pub account: Option<Account>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionSummary {
+    pub id: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub deployed: bool,
+    /// When the version was last deployed, if ever
+    pub deployed_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct VersionsResponse {
+    pub err: Option<ErrorDetails>,
+    #[serde(default)]
+    pub versions: Vec<VersionSummary>,
 }
 
 #[derive(Deserialize)]
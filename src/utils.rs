@@ -1,9 +1,17 @@
 use crate::api_service::ApiService;
+use crate::errors::GhostError;
+use crate::output::{self, say};
 use crate::types::{Chain, GraphConfig, GraphFile};
+use alloy_primitives::keccak256;
+use chrono::{DateTime, Local};
+use dialoguer::Confirm;
 use dirs::cache_dir;
 use eyre::{eyre, EyreHandler};
+use serde_json::{json, Value};
+use similar::{ChangeTag, TextDiff};
 use std::error::Error;
 use std::fs::{self};
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use tracing::debug;
 use yansi::Paint;
@@ -14,9 +22,7 @@ pub async fn check_and_get_conf(
 ) -> eyre::Result<GraphConfig> {
     let path = Path::new("config.json");
     if !path.exists() {
-        return Err(eyre!(
-            "config.json not found. This command can only be run in a ghost directory"
-        ));
+        return Err(GhostError::NotGhostDir.into());
     }
 
     for &file in required_files {
@@ -42,7 +48,7 @@ pub fn check_and_create_dir(dir: &PathBuf) -> eyre::Result<()> {
         fs::create_dir_all(dir)?;
     }
     let dir = dunce::canonicalize(dir)?;
-    if dir.read_dir().map_or(false, |mut i| i.next().is_some()) {
+    if dir.read_dir().is_ok_and(|mut i| i.next().is_some()) {
         eyre::bail!("Cannot run `create` on a non-empty directory");
     }
     Ok(())
@@ -57,6 +63,57 @@ pub fn write_files(dir: &Path, sources: Vec<GraphFile>) -> eyre::Result<()> {
     Ok(())
 }
 
+/// Reads every file under `dir/src`, with paths relative to `src/`.
+///
+/// Hidden files and directories (`.DS_Store`, `.foo.sol.swp`) are ignored, and files that aren't
+/// UTF-8 text are skipped with a warning.
+pub fn read_files(dir: &Path) -> eyre::Result<Vec<GraphFile>> {
+    fn visit(root: &Path, dir: &Path, files: &mut Vec<GraphFile>) -> eyre::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                visit(root, &path, files)?;
+                continue;
+            }
+            let rel = path.strip_prefix(root)?.components();
+            let rel = rel.map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            match String::from_utf8(fs::read(&path)?) {
+                Ok(code) => files.push(GraphFile { path: rel, code }),
+                Err(_) => eprintln!(
+                    "{}",
+                    format!("Warning: skipping src/{}, which is not a UTF-8 text file", rel)
+                        .yellow()
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    let src = dir.join("src");
+    let mut files = Vec::new();
+    visit(&src, &src, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Writes `sources` and records them in `config` as the last version synced with the server.
+pub fn write_synced_files(
+    dir: &Path,
+    config: &mut GraphConfig,
+    sources: Vec<GraphFile>,
+) -> eyre::Result<()> {
+    for source in &sources {
+        config.synced.insert(source.path.clone(), source_hash(&source.code));
+    }
+    write_files(dir, sources)?;
+    config.write(dir.join("config.json"))?;
+    Ok(())
+}
+
 pub fn write_sources_and_conf(
     dir: &Path,
     id: String,
@@ -64,11 +121,111 @@ pub fn write_sources_and_conf(
     chain: Option<Chain>,
     sources: Vec<GraphFile>,
 ) -> eyre::Result<()> {
-    write_files(dir, sources)?;
-    GraphConfig { id, version_id, chain }.write(dir.join("config.json"))?;
+    let mut config = GraphConfig { id, version_id, chain, synced: Default::default() };
+    write_synced_files(dir, &mut config, sources)
+}
+
+/// Compares `local` against `remote` sources and returns `(status, path)` for each file that
+/// differs, where status is `modified`, `new` (local only) or `missing` (remote only).
+pub fn diff_sources(local: &[GraphFile], remote: &[GraphFile]) -> Vec<(&'static str, String)> {
+    let mut changes = Vec::new();
+    for source in local {
+        match remote.iter().find(|r| r.path == source.path) {
+            Some(r) if r.code == source.code => {}
+            Some(_) => changes.push(("modified", source.path.clone())),
+            None => changes.push(("new", source.path.clone())),
+        }
+    }
+    for source in remote {
+        if !local.iter().any(|l| l.path == source.path) {
+            changes.push(("missing", source.path.clone()));
+        }
+    }
+    changes
+}
+
+/// Prints `(status, path)` changes as an aligned list, one file per line.
+pub fn print_changes(changes: &[(&str, String)]) {
+    for (status, path) in changes {
+        say!("  {:<9} {}", format!("{status}:"), path);
+    }
+}
+
+/// Converts `(status, path)` changes into the `changes` array of the JSON output.
+pub fn changes_json(changes: &[(&str, String)]) -> Vec<Value> {
+    changes.iter().map(|(status, path)| json!({ "path": path, "status": status })).collect()
+}
+
+/// Fails if writing `sources` would overwrite files in `src/` that were edited since the last
+/// sync, unless `force` is set.
+pub fn check_local_edits(
+    config: &GraphConfig,
+    sources: &[GraphFile],
+    force: bool,
+) -> eyre::Result<()> {
+    let src = Path::new("src");
+    let conflicts: Vec<_> = sources
+        .iter()
+        .filter(|source| match fs::read_to_string(src.join(&source.path)) {
+            Ok(local) => {
+                local != source.code
+                    && config.synced.get(&source.path) != Some(&source_hash(&local))
+            }
+            Err(_) => false,
+        })
+        .map(|source| source.path.as_str())
+        .collect();
+    if !conflicts.is_empty() && !force {
+        eyre::bail!(
+            "Local edits in {} would be overwritten. Re-run with --force to discard them",
+            conflicts.join(", ")
+        );
+    }
     Ok(())
 }
 
+/// Asks the user to confirm `prompt`, or returns true right away if `yes` is set.
+///
+/// Fails when there is nobody to ask (JSON output or stdin is not a terminal).
+pub fn confirm(prompt: &str, yes: bool) -> eyre::Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if output::is_json() || !std::io::stdin().is_terminal() {
+        eyre::bail!("{} Pass --yes to confirm non-interactively", prompt);
+    }
+    Ok(Confirm::new().with_prompt(prompt).default(false).interact()?)
+}
+
+pub fn source_hash(code: &str) -> String {
+    keccak256(code.as_bytes()).to_string()
+}
+
+/// Prints a colored unified diff of `path`, labelling the two sides with `labels`.
+pub fn print_diff(path: &str, old: &str, new: &str, labels: [&str; 2]) {
+    let diff = TextDiff::from_lines(old, new);
+    let [old_label, new_label] = labels;
+    say!("{}", format!("--- {old_label}/{path}\n+++ {new_label}/{path}").bold());
+    for hunk in diff.unified_diff().iter_hunks() {
+        say!("{}", hunk.header().cyan());
+        for change in hunk.iter_changes() {
+            let line = change.value().trim_end_matches('\n');
+            match change.tag() {
+                ChangeTag::Delete => say!("{}", format!("-{line}").red()),
+                ChangeTag::Insert => say!("{}", format!("+{line}").green()),
+                ChangeTag::Equal => say!(" {line}"),
+            }
+        }
+    }
+}
+
+/// Formats an RFC 3339 timestamp from the API in local time.
+pub fn fmt_time(dt: &str) -> Option<String> {
+    let utc_time = DateTime::parse_from_rfc3339(dt).ok()?;
+    let local_time = utc_time.with_timezone(&Local);
+    Some(local_time.format("%Y-%m-%d %H:%M").to_string())
+}
+
 pub fn cache_path() -> Option<PathBuf> {
     let path = cache_dir()?.join("ghost");
     fs::create_dir_all(&path).ok()?;